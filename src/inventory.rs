@@ -1,5 +1,4 @@
 use ink_prelude::vec::Vec;
-use ink_prelude::borrow::ToOwned;
 
 pub type ItemId = u32;
 
@@ -13,7 +12,64 @@ pub enum ResourceType {
     Copper,
     Silver,
     Gold,
-    Uranium,    
+    Uranium,
+    Steel, // Refined from iron
+}
+
+// ALL_RESOURCE_TYPES lists every resource type in declaration order
+pub const ALL_RESOURCE_TYPES: [ResourceType; 6] = [
+    ResourceType::Iron,
+    ResourceType::Copper,
+    ResourceType::Silver,
+    ResourceType::Gold,
+    ResourceType::Uranium,
+    ResourceType::Steel,
+];
+
+// max_stack_size is the max quantity of a single resource stack
+// heavy resources stack lower
+pub fn max_stack_size(resource_type: &ResourceType) -> u32 {
+    match resource_type {
+        ResourceType::Iron => 64,
+        ResourceType::Copper => 64,
+        ResourceType::Silver => 64,
+        ResourceType::Gold => 32,
+        ResourceType::Uranium => 16,
+        ResourceType::Steel => 32,
+    }
+}
+
+// resource_metadata returns the (value, weight) of a single unit of the resource
+// weight times max stack size is the same for all types, so a full stack always weighs 64
+pub fn resource_metadata(resource_type: &ResourceType) -> (u32, u32) {
+    match resource_type {
+        ResourceType::Iron => (1, 1),
+        ResourceType::Copper => (2, 1),
+        ResourceType::Silver => (5, 1),
+        ResourceType::Gold => (10, 2),
+        ResourceType::Uranium => (25, 4),
+        ResourceType::Steel => (4, 2),
+    }
+}
+
+// refining_recipe returns the refined resource type and how many units of the input
+// are needed for one unit of it, or None if the resource can't be refined
+pub fn refining_recipe(resource_type: &ResourceType) -> Option<(ResourceType, u32)> {
+    match resource_type {
+        ResourceType::Iron => Some((ResourceType::Steel, 3)),
+        _ => None,
+    }
+}
+
+const WEAPON_WEIGHT: u32 = 10;
+const ARMOR_WEIGHT: u32 = 20;
+
+fn item_weight(item: &Item) -> u32 {
+    match item {
+        Item::Weapon(_) => WEAPON_WEIGHT,
+        Item::Armor(_) => ARMOR_WEIGHT,
+        Item::Resource(r) => r.quantity * resource_metadata(&r.resource_type).1,
+    }
 }
 
 // Items are either something in the inventory or in the cargo
@@ -40,6 +96,9 @@ pub struct Weapon {
     damage: u32,      // Damage of the weapon
     range: u32,       // Range of the weapon
     energy_cost: u32, // Energy consumed by firing the weapon
+    blast_radius: u32, // Tiles around the target that are hit as well
+    cooldown: u32,     // Blocks until the weapon can be fired again
+    ready_at: u32,     // Block the weapon can be fired again at, kept when the weapon changes hands
 }
 
 // Armors are used to defend against attacks
@@ -73,6 +132,8 @@ pub struct Resource {
 pub struct Inventory {
     items: Vec<Item>,
     max_size: u32,
+    next_id: ItemId, // Item ids are unique within the inventory holding the item
+    weight_limit: Option<u32>, // Set for weighted inventories, which aren't limited by slots
 }
 
 impl Item {
@@ -83,56 +144,562 @@ impl Item {
             Item::Resource(resource) => resource.id,
         }
     }
+
+    // value is what the item is worth, resources by type and quantity, equipment by its stats
+    pub fn value(&self) -> u32 {
+        match self {
+            Item::Weapon(weapon) => weapon.damage * 2 + weapon.range * 5,
+            Item::Armor(armor) => armor.defense * 3,
+            Item::Resource(resource) => {
+                resource.quantity * resource_metadata(&resource.resource_type).0
+            }
+        }
+    }
+
+    fn set_id(&mut self, id: ItemId) {
+        match self {
+            Item::Weapon(weapon) => weapon.id = id,
+            Item::Armor(armor) => armor.id = id,
+            Item::Resource(resource) => resource.id = id,
+        }
+    }
 }
 
 pub enum Error {
     InventoryFull,
+    ItemNotFound,
+    NotStackable,
+    NotEnoughResources,
+    InvalidQuantity,
+}
+
+impl Weapon {
+    pub fn new(damage: u32, range: u32, energy_cost: u32) -> Self {
+        Self { id: 0, damage, range, energy_cost, blast_radius: 0, cooldown: 0, ready_at: 0 }
+    }
+
+    pub fn with_cooldown(mut self, cooldown: u32) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn cooldown(&self) -> u32 {
+        self.cooldown
+    }
+
+    pub fn ready_at(&self) -> u32 {
+        self.ready_at
+    }
+
+    // fired starts the cooldown of the weapon at the given block
+    pub fn fired(&mut self, block: u32) {
+        self.ready_at = block.saturating_add(self.cooldown);
+    }
+
+    pub fn with_blast_radius(mut self, blast_radius: u32) -> Self {
+        self.blast_radius = blast_radius;
+        self
+    }
+
+    pub fn blast_radius(&self) -> u32 {
+        self.blast_radius
+    }
+
+    pub fn damage(&self) -> u32 {
+        self.damage
+    }
+
+    pub fn range(&self) -> u32 {
+        self.range
+    }
+
+    pub fn energy_cost(&self) -> u32 {
+        self.energy_cost
+    }
+}
+
+impl Armor {
+    pub fn new(defense: u32) -> Self {
+        Self { id: 0, defense }
+    }
+
+    pub fn defense(&self) -> u32 {
+        self.defense
+    }
 }
 
 impl Resource {
     pub fn new(resource_type: ResourceType, quantity: u32) -> Self {
         Self { id: 0, resource_type, quantity }
     }
+
+    pub fn resource_type(&self) -> ResourceType {
+        self.resource_type.clone()
+    }
+
+    pub fn quantity(&self) -> u32 {
+        self.quantity
+    }
 }
 
 impl Inventory {
     pub fn new(max_size: u32) -> Self {
-        Self { items: Vec::new(), max_size }
+        Self { items: Vec::new(), max_size, next_id: 1, weight_limit: None }
+    }
+
+    // new_weighted creates an inventory that is limited by the total weight of its items
+    // instead of the number of slots
+    pub fn new_weighted(max_weight: u32) -> Self {
+        Self { items: Vec::new(), max_size: u32::MAX, next_id: 1, weight_limit: Some(max_weight) }
+    }
+
+    // weight sums up the resource weights and fixed weights for weapons and armor
+    pub fn weight(&self) -> u32 {
+        self.items.iter().map(item_weight).sum()
+    }
+
+    // max_weight is the weight limit of weighted inventories,
+    // otherwise the weight of a full inventory of standard stacks
+    pub fn max_weight(&self) -> u32 {
+        self.weight_limit.unwrap_or(self.max_size.saturating_mul(64))
+    }
+
+    pub fn value(&self) -> u32 {
+        self.items.iter().map(|item| item.value()).sum()
+    }
+
+    pub fn items(&self) -> Vec<Item> {
+        self.items.clone()
+    }
+
+    // is_full tells whether nothing fits anymore, neither in a free slot
+    // nor on a resource stack that has room left
+    pub fn is_full(&self) -> bool {
+        if let Some(limit) = self.weight_limit {
+            return self.weight() >= limit;
+        }
+        self.items.len() >= self.max_size as usize
+            && self.items.iter().all(|item| match item {
+                Item::Resource(r) => r.quantity >= max_stack_size(&r.resource_type),
+                _ => true,
+            })
+    }
+
+    // has_space tells whether add_item would take the item, resources count the room
+    // left on their stacks, everything else needs a free slot
+    pub fn has_space(&self, item: &Item) -> bool {
+        match item {
+            Item::Resource(r) => self.resource_space(&r.resource_type) >= r.quantity,
+            item => {
+                self.items.len() < self.max_size as usize
+                    && self
+                        .weight_limit
+                        .is_none_or(|limit| self.weight() + item_weight(item) <= limit)
+            }
+        }
+    }
+
+    // set_max_size only grows the inventory, it never drops items
+    pub fn set_max_size(&mut self, max_size: u32) {
+        if max_size > self.max_size {
+            self.max_size = max_size;
+        }
+    }
+
+    fn new_id(&mut self) -> ItemId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
     }
 
     // add_item adds the item and stacks it if possible
-    // max_size is respected 
+    // newly added items and stacks get a fresh id
+    // max_size is respected, the item is either added completely or not at all
     // only resources are stackable
-    // max stack size for resources is 64
-    pub fn add_item(&mut self, item: Item) -> Result<(), Error>{
-        if self.items.len() >= self.max_size as usize {
+    // max stack size for resources depends on the resource type
+    // returns the id the item has now, for resources the id of the last stack they went into
+    pub fn add_item(&mut self, item: Item) -> Result<ItemId, Error> {
+        let resource = match item {
+            Item::Resource(resource) => resource,
+            mut item => {
+                if self.items.len() >= self.max_size as usize {
+                    return Err(Error::InventoryFull);
+                }
+                if self.weight_limit.is_some_and(|limit| self.weight() + item_weight(&item) > limit) {
+                    return Err(Error::InventoryFull);
+                }
+                let id = self.new_id();
+                item.set_id(id);
+                self.items.push(item);
+                return Ok(id);
+            }
+        };
+
+        let stack_size = max_stack_size(&resource.resource_type);
+
+        // make sure the whole quantity fits before touching any stack
+        if self.resource_space(&resource.resource_type) < resource.quantity {
             return Err(Error::InventoryFull);
         }
-        let mut item = item;
-        if let Item::Resource(resource) = &mut item {
-            let mut found = false;
-            for item in self.items.iter_mut() {
-                if let Item::Resource(r) = item {
-                    if r.resource_type == resource.resource_type {
-                        r.quantity += resource.quantity;
-                        if r.quantity > 64 {
-                            resource.quantity = r.quantity - 64;
-                            r.quantity = 64;
-                        } else {
-                            found = true;
-                            break;
-                        }
-                    }
+
+        // fill up existing stacks first, then open new ones
+        let mut rest = resource.quantity;
+        let mut last_id = 0;
+        for item in self.items.iter_mut() {
+            if rest == 0 {
+                break;
+            }
+            if let Item::Resource(r) = item {
+                if r.resource_type == resource.resource_type && r.quantity < stack_size {
+                    let amount = rest.min(stack_size - r.quantity);
+                    r.quantity += amount;
+                    rest -= amount;
+                    last_id = r.id;
+                }
+            }
+        }
+        while rest > 0 {
+            let amount = rest.min(stack_size);
+            let id = self.new_id();
+            self.items.push(Item::Resource(Resource {
+                id,
+                resource_type: resource.resource_type.clone(),
+                quantity: amount,
+            }));
+            rest -= amount;
+            last_id = id;
+        }
+        Ok(last_id)
+    }
+
+    // resource_space is the quantity of the resource type that still fits,
+    // counting free room on existing stacks and free slots
+    pub fn resource_space(&self, resource_type: &ResourceType) -> u32 {
+        let stack_size = max_stack_size(resource_type);
+        let free_slots = (self.max_size as usize).saturating_sub(self.items.len()) as u32;
+        let mut space = free_slots.saturating_mul(stack_size);
+        for item in self.items.iter() {
+            if let Item::Resource(r) = item {
+                if r.resource_type == *resource_type {
+                    space = space.saturating_add(stack_size.saturating_sub(r.quantity));
                 }
             }
-            if !found {
-                self.items.push(Item::Resource(resource.to_owned()));
+        }
+        match self.weight_limit {
+            Some(limit) => {
+                let unit_weight = resource_metadata(resource_type).1;
+                space.min(limit.saturating_sub(self.weight()) / unit_weight)
+            }
+            None => space,
+        }
+    }
+
+    // count_resource sums up the quantity of the resource type across all stacks
+    pub fn count_resource(&self, resource_type: &ResourceType) -> u32 {
+        self.items
+            .iter()
+            .map(|item| match item {
+                Item::Resource(r) if r.resource_type == *resource_type => r.quantity,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    // consume_resource removes amount of the resource type across all stacks
+    // nothing is removed if there isn't enough of it
+    pub fn consume_resource(
+        &mut self,
+        resource_type: &ResourceType,
+        amount: u32,
+    ) -> Result<(), Error> {
+        if self.count_resource(resource_type) < amount {
+            return Err(Error::NotEnoughResources);
+        }
+        let mut rest = amount;
+        for item in self.items.iter_mut().rev() {
+            if rest == 0 {
+                break;
+            }
+            if let Item::Resource(r) = item {
+                if r.resource_type == *resource_type {
+                    let taken = rest.min(r.quantity);
+                    r.quantity -= taken;
+                    rest -= taken;
+                }
             }
-        } else {
-            self.items.push(item)
         }
+        self.items
+            .retain(|item| !matches!(item, Item::Resource(r) if r.quantity == 0));
         Ok(())
     }
 
+    // split_stack takes amount off a resource stack and puts it into a new stack
+    // returns the id of the new stack
+    pub fn split_stack(&mut self, item_id: ItemId, amount: u32) -> Result<ItemId, Error> {
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.id() == item_id)
+            .ok_or(Error::ItemNotFound)?;
+        let quantity = match &self.items[index] {
+            Item::Resource(r) => r.quantity,
+            _ => return Err(Error::NotStackable),
+        };
+        if quantity < amount {
+            return Err(Error::NotEnoughResources);
+        }
+        if amount == 0 || amount == quantity {
+            return Err(Error::InvalidQuantity);
+        }
+        if self.items.len() >= self.max_size as usize {
+            return Err(Error::InventoryFull);
+        }
+        let id = self.new_id();
+        if let Item::Resource(r) = &mut self.items[index] {
+            r.quantity -= amount;
+            let resource_type = r.resource_type.clone();
+            self.items.push(Item::Resource(Resource {
+                id,
+                resource_type,
+                quantity: amount,
+            }));
+        }
+        Ok(id)
+    }
+
+    pub fn get_item(&self, item_id: ItemId) -> Option<&Item> {
+        self.items.iter().find(|item| item.id() == item_id)
+    }
+
+    pub fn get_item_mut(&mut self, item_id: ItemId) -> Option<&mut Item> {
+        self.items.iter_mut().find(|item| item.id() == item_id)
+    }
+
+    // remove_item takes the item with the given id out of the inventory
+    pub fn remove_item(&mut self, item_id: ItemId) -> Option<Item> {
+        let index = self.items.iter().position(|item| item.id() == item_id)?;
+        Some(self.items.remove(index))
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacking_respects_max_stack_size() {
+        let mut inventory = Inventory::new(2);
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 100)))
+            .is_ok());
+        assert_eq!(inventory.items.len(), 2);
+
+        // uranium stacks at 16, so 40 needs three stacks
+        let mut inventory = Inventory::new(2);
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Uranium, 40)))
+            .is_err());
+        assert!(inventory.items.is_empty());
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Uranium, 20)))
+            .is_ok());
+        let quantities: Vec<u32> = inventory
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Resource(r) => r.quantity,
+                _ => 0,
+            })
+            .collect();
+        assert_eq!(quantities, vec![16, 4]);
+    }
+
+    #[test]
+    fn all_resource_types_covers_every_variant() {
+        // the match fails to compile when a variant is added, as a reminder to list it
+        let last = |resource_type: &ResourceType| match resource_type {
+            ResourceType::Iron
+            | ResourceType::Copper
+            | ResourceType::Silver
+            | ResourceType::Gold
+            | ResourceType::Uranium => false,
+            ResourceType::Steel => true,
+        };
+        assert!(last(&ALL_RESOURCE_TYPES[ALL_RESOURCE_TYPES.len() - 1]));
+        assert_eq!(ALL_RESOURCE_TYPES.len(), ResourceType::Steel as usize + 1);
+        for (index, resource_type) in ALL_RESOURCE_TYPES.iter().enumerate() {
+            assert_eq!(resource_type.clone() as usize, index);
+        }
+    }
+
+    #[test]
+    fn weighted_inventory_limits_by_weight() {
+        // two slots hold two stacks, no matter how heavy they are
+        let mut slots = Inventory::new(2);
+        assert!(slots
+            .add_item(Item::Resource(Resource::new(ResourceType::Uranium, 32)))
+            .is_ok());
+        assert!(slots
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 1)))
+            .is_err());
+
+        // the same weight limit takes many light stacks but few heavy units
+        let mut weighted = Inventory::new_weighted(128);
+        assert_eq!(weighted.max_weight(), 128);
+        assert_eq!(weighted.resource_space(&ResourceType::Uranium), 32);
+        assert!(weighted
+            .add_item(Item::Resource(Resource::new(ResourceType::Uranium, 33)))
+            .is_err());
+        for resource_type in [ResourceType::Iron, ResourceType::Copper, ResourceType::Silver] {
+            assert!(weighted
+                .add_item(Item::Resource(Resource::new(resource_type, 30)))
+                .is_ok());
+        }
+        assert_eq!(weighted.items.len(), 3);
+        assert_eq!(weighted.resource_space(&ResourceType::Gold), 19);
+        assert!(weighted.add_item(Item::Armor(Armor::new(1))).is_ok());
+        assert!(weighted.add_item(Item::Weapon(Weapon::new(1, 1, 1))).is_ok());
+        assert_eq!(weighted.weight(), 120);
+        assert!(weighted.add_item(Item::Armor(Armor::new(1))).is_err());
+    }
+
+    #[test]
+    fn resource_metadata_values_and_weights() {
+        let (iron_value, _) = resource_metadata(&ResourceType::Iron);
+        let (uranium_value, _) = resource_metadata(&ResourceType::Uranium);
+        assert!(uranium_value > iron_value);
+
+        let mut inventory = Inventory::new(2);
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Uranium, 16)))
+            .is_ok());
+        assert_eq!(inventory.weight(), 64);
+    }
+
+    #[test]
+    fn unstackable_items_fill_slots() {
+        let weapon = Item::Weapon(Weapon::new(1, 1, 1));
+        let iron = Item::Resource(Resource::new(ResourceType::Iron, 1));
+        let mut inventory = Inventory::new(2);
+        assert!(!inventory.is_full());
+        assert!(inventory.has_space(&weapon));
+        assert!(inventory.add_item(weapon.clone()).is_ok());
+        assert!(inventory.has_space(&weapon));
+        assert!(inventory.add_item(Item::Armor(Armor::new(1))).is_ok());
+        assert!(inventory.is_full());
+        assert!(!inventory.has_space(&weapon));
+        assert!(!inventory.has_space(&iron));
+    }
+
+    #[test]
+    fn resource_stacks_count_until_topped_up() {
+        let mut inventory = Inventory::new(2);
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 100)))
+            .is_ok());
+        // both slots are taken, but the second stack has room left
+        let rest = Item::Resource(Resource::new(ResourceType::Iron, 28));
+        assert!(!inventory.has_space(&Item::Weapon(Weapon::new(1, 1, 1))));
+        assert!(!inventory.has_space(&Item::Resource(Resource::new(ResourceType::Gold, 1))));
+        assert!(!inventory.has_space(&Item::Resource(Resource::new(ResourceType::Iron, 29))));
+        assert!(inventory.has_space(&rest));
+        assert!(!inventory.is_full());
+        assert!(inventory.add_item(rest.clone()).is_ok());
+        assert!(inventory.is_full());
+        assert!(!inventory.has_space(&rest));
+
+        let mut weighted = Inventory::new_weighted(8);
+        assert!(!weighted.is_full());
+        assert!(weighted.has_space(&Item::Resource(Resource::new(ResourceType::Iron, 8))));
+        assert!(!weighted.has_space(&Item::Resource(Resource::new(ResourceType::Iron, 9))));
+        assert!(weighted
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 8)))
+            .is_ok());
+        assert!(weighted.is_full());
+    }
+
+    #[test]
+    fn count_resource_sums_stacks() {
+        let mut inventory = Inventory::new(4);
+        assert_eq!(inventory.count_resource(&ResourceType::Iron), 0);
+        for quantity in [64, 64, 7] {
+            assert!(inventory
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, quantity)))
+                .is_ok());
+        }
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Gold, 3)))
+            .is_ok());
+        assert_eq!(inventory.items.len(), 4);
+        assert_eq!(inventory.count_resource(&ResourceType::Iron), 135);
+        assert_eq!(inventory.count_resource(&ResourceType::Gold), 3);
+        assert_eq!(inventory.count_resource(&ResourceType::Copper), 0);
+    }
+
+    #[test]
+    fn consume_resource_spans_stacks() {
+        let mut inventory = Inventory::new(4);
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 64)))
+            .is_ok());
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 20)))
+            .is_ok());
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Copper, 5)))
+            .is_ok());
+
+        assert!(inventory.consume_resource(&ResourceType::Iron, 30).is_ok());
+        let iron: Vec<u32> = inventory
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Resource(r) if r.resource_type == ResourceType::Iron => Some(r.quantity),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(iron, vec![54]);
+        assert_eq!(inventory.items.len(), 2);
+    }
+
+    #[test]
+    fn add_item_returns_the_new_id() {
+        let mut inventory = Inventory::new(4);
+        let mut add = |item| inventory.add_item(item).ok();
+        assert_eq!(add(Item::Resource(Resource::new(ResourceType::Iron, 60))), Some(1));
+        // tops up the first stack and opens a second one
+        assert_eq!(add(Item::Resource(Resource::new(ResourceType::Iron, 10))), Some(2));
+        assert_eq!(add(Item::Resource(Resource::new(ResourceType::Iron, 2))), Some(2));
+        assert_eq!(add(Item::Armor(Armor::new(1))), Some(3));
+    }
+
+    #[test]
+    fn consume_resource_fails_without_partial_removal() {
+        let mut inventory = Inventory::new(4);
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 64)))
+            .is_ok());
+        assert!(inventory
+            .add_item(Item::Resource(Resource::new(ResourceType::Iron, 20)))
+            .is_ok());
+
+        assert!(matches!(
+            inventory.consume_resource(&ResourceType::Iron, 85),
+            Err(Error::NotEnoughResources)
+        ));
+        assert!(matches!(
+            inventory.consume_resource(&ResourceType::Copper, 1),
+            Err(Error::NotEnoughResources)
+        ));
+        let total: u32 = inventory
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Resource(r) => r.quantity,
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(total, 84);
+        assert_eq!(inventory.items.len(), 2);
+    }
+}
@@ -1,27 +1,81 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod inventory;
+mod mechanics;
 mod planets;
 
 #[ink::contract]
 mod rareships {
-    use ink::prelude::string::{String, ToString};
+    use ink::prelude::boxed::Box;
+    #[cfg(feature = "debug-events")]
+    use ink::prelude::format;
+    use ink::prelude::string::String;
+    #[cfg(feature = "debug-events")]
+    use ink::prelude::string::ToString;
     use ink::prelude::vec::Vec;
 
     use ink::storage::{Lazy, Mapping};
     use scale::{Decode, Encode};
 
-    use crate::inventory::{Inventory, Item, Resource, ResourceType};
+    use crate::inventory::{
+        max_stack_size, refining_recipe, resource_metadata, ALL_RESOURCE_TYPES, Inventory, Item, ItemId, Resource, ResourceType,
+        Weapon,
+    };
+    use crate::mechanics::{
+        crosses_edge, cube_coordinates_to_offset_coordinates, cube_direction, cube_length,
+        direction_along, direction_to, hex_distance, hex_neighbors, in_bounds, move_energy_per_tile,
+        move_in_direction, loaded_speed, offset_coordinates_to_cube_coordinates, step_towards,
+        step_wraps, tiles_in_range, wrap_position, wrapped_offset, xp_for_level, Direction,
+    };
     use crate::planets::{Planet, PlanetId, PlanetLevel};
 
-    const MAX_X: i32 = 10000;
-    const MAX_Y: i32 = 10000;
+    // VERSION of the contract code, compared against the stored version on migrations
+    const VERSION: &str = env!("CARGO_PKG_VERSION");
+    const DEFAULT_MAX_X: i32 = 10000;
+    const DEFAULT_MAX_Y: i32 = 10000;
+    const DEFAULT_SPAWN_COST: Balance = 0;
+    const DEFAULT_CLAIM_EXPIRY: Block = 100_000;
+    const DEFAULT_MINE_ENERGY_COST: u32 = 100;
+    const DOCKED_RECHARGE_MULTIPLIER: u32 = 2;
+    const MAX_EQUIPPED_ITEMS: usize = 3;
+    const MAX_ORDERS: usize = 16;
+    const MAX_SHIPS_PER_ACCOUNT: usize = 8;
+    const MAX_LEADERBOARD_LIMIT: u32 = 20;
+    const LEVEL_HEALTH_BONUS: u32 = 10;
+    const LEVEL_ENERGY_BONUS: u32 = 50;
+    // new ships start with empty shields that charge up over time
+    const DEFAULT_MAX_SHIELD: u32 = 50;
+    const DEFAULT_SHIELD_REGEN_RATE: u32 = 5;
+    // ships can mine planets up to this many tiles away
+    const MINING_RANGE: i32 = 1;
+    // share of the mined resources that goes to the planet owner if someone else mines
+    const MINING_FEE_PERCENT: u32 = 20;
+    // units an asteroid yields per block of mining
+    const ASTEROID_MINING_RATE: u32 = 2;
+    // energy needed to refine one unit of output
+    const REFINE_ENERGY_PER_UNIT: u32 = 10;
+    // ships starting or ending a move within GRAVITY_RADIUS tiles of a planet only reach this share of their speed
+    const GRAVITY_RADIUS: i32 = 2;
+    const GRAVITY_SPEED_PERCENT: i32 = 50;
+    const SPEED_UPGRADE_STEP: u32 = 1000;
+    const MAX_SPEED_CAP: u32 = 20000;
+    const ENERGY_UPGRADE_STEP: u32 = 100;
+    const MAX_ENERGY_CAP: u32 = 5000;
+    const RECHARGE_UPGRADE_STEP: u32 = 2;
+    const MAX_RECHARGE_RATE_CAP: u32 = 50;
+    const CARGO_UPGRADE_STEP: u32 = 8;
+    const MAX_CARGO_SIZE_CAP: u32 = 64;
+    const INVENTORY_UPGRADE_STEP: u32 = 2;
+    const MAX_INVENTORY_SIZE_CAP: u32 = 16;
 
     type ShipId = u32;
     type Speed = i32;
     type Distance = i32;
     type Block = u32;
     type Duration = u32;
+    type MinedTotals = Vec<(ResourceType, u64)>;
+    type TileShips = Vec<ShipId>;
+    type Asteroid = (ResourceType, u32); // Resource and the units left
 
     #[derive(Debug, PartialEq, Eq, Encode, Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -39,14 +93,39 @@ mod rareships {
         PlanetAlreadyExists,
         NotAuthorized,
         PlanetNotFound,
+        PlanetAlreadyOwned,
         ResourceNotFound,
         NotPlanetOwner,
+        ShipNotAtPlanet,
+        ShipLimitReached,
+        InsufficientPayment,
+        TargetOutOfRange,
+        NameTaken,
+        WeaponOnCooldown,
+        ShipsNotColocated,
+        ItemNotFound,
+        EquipSlotsFull,
+        UpgradeLimitReached,
+        TooManyOrders,
+        NoBounty,
+        TransferFailed,
+        WreckNotFound,
+        NotAtWreck,
+        TradeNotFound,
+        NotForSale,
+        AsteroidNotFound,
+        OutOfBounds,
+        CodeUpgradeFailed,
     }
 
     impl From<crate::inventory::Error> for Error {
         fn from(error: crate::inventory::Error) -> Self {
             match error {
                 crate::inventory::Error::InventoryFull => Error::NotEnoughInventorySpace,
+                crate::inventory::Error::ItemNotFound => Error::ItemNotFound,
+                crate::inventory::Error::NotStackable => Error::InvalidOrder,
+                crate::inventory::Error::NotEnoughResources => Error::NotEnoughResources,
+                crate::inventory::Error::InvalidQuantity => Error::InvalidOrder,
             }
         }
     }
@@ -68,14 +147,106 @@ mod rareships {
         max_energy: u32,         // Max energy of the ship
         max_health: u32,         // Max health of the ship
         recharge_rate: u32,      // Energy recharge rate of the ship per block
+        max_shield: u32,         // Max shield of the ship
+        shield_regen_rate: u32,  // Shield regeneration per block
 
         position: (i32, i32),                // Position of the ship
         energy: u32,                         // Current energy of the ship
         health: u32,                         // Current health of the ship
+        shield: u32,                         // Current shield, absorbs damage before the health
         inventory: Inventory,                // Inventory of the ship
         cargo: Inventory,                    // Cargo of the ship
-        orders: Vec<(Order, Option<Block>)>, // Orders of the ship and when they were started.
+        orders: Vec<(Order, Option<Block>, Option<Block>)>, // Orders of the ship, when they were started and their deadline
         last_recharge: Block,                // Block where the last recharge was settled
+        last_shield_regen: Block,            // Block where the last shield regeneration was settled
+        move_progress_millis: i32,           // Sub-tile progress of the current move, milli-tiles
+        equipped: Vec<ItemId>,               // Weapons and armor in the inventory that are in use
+        docked_at: Option<PlanetId>,         // Planet the ship is docked at, it can't move while docked
+        xp: u32,                             // Experience from mining and combat
+        level: u32,                          // Level reached with the experience
+        home_planet: Option<PlanetId>,       // Base the ship returns to
+    }
+
+    // Read-only access to the ship, so logic outside of the contract module doesn't
+    // depend on the field layout
+    impl Ship {
+        pub(crate) fn id(&self) -> ShipId {
+            self.id
+        }
+
+        pub(crate) fn name(&self) -> &str {
+            &self.name
+        }
+
+        pub(crate) fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        pub(crate) fn position(&self) -> (i32, i32) {
+            self.position
+        }
+
+        pub(crate) fn energy(&self) -> u32 {
+            self.energy
+        }
+
+        pub(crate) fn max_energy(&self) -> u32 {
+            self.max_energy
+        }
+
+        pub(crate) fn health(&self) -> u32 {
+            self.health
+        }
+
+        pub(crate) fn max_health(&self) -> u32 {
+            self.max_health
+        }
+
+        pub(crate) fn orders(&self) -> &[(Order, Option<Block>, Option<Block>)] {
+            &self.orders
+        }
+
+        pub(crate) fn inventory(&self) -> &Inventory {
+            &self.inventory
+        }
+
+        pub(crate) fn cargo(&self) -> &Inventory {
+            &self.cargo
+        }
+    }
+
+    // Just enough of a ship to render it in a fleet list
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct ShipSummary {
+        pub id: ShipId,
+        pub name: String,
+        pub owner: AccountId,
+        pub position: (i32, i32),
+        pub energy: u32,
+        pub health: u32,
+        pub order_count: u32,
+    }
+
+    // Top-line numbers of the contract
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct ContractStats {
+        pub ship_count: u32,
+        pub planet_count: u32,
+        pub admin: AccountId,
+    }
+
+    // A resource swap proposed by a docked ship, the offer is locked until the trade is accepted
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Trade {
+        pub ship_id: ShipId,              // Ship that proposed the trade
+        pub offer: (ResourceType, u32),   // Taken out of the proposer's cargo
+        pub request: (ResourceType, u32), // Wanted in exchange
     }
 
     // Orders are used to instruct what the ship should do next
@@ -86,22 +257,33 @@ mod rareships {
     )]
     pub enum Order {
         Move((Direction, Speed, Distance)), // Move to in a direction
+        MoveBy(((i32, i32, i32), Speed)), // Move by a cube coordinate delta
         Mine((PlanetId, ResourceType, Duration)),
+        MineUntilFull((PlanetId, ResourceType)), // Mine until the cargo can't take more
+        MineAll((PlanetId, Duration)), // Mine every resource the planet offers
+        MineAndDeposit((PlanetId, ResourceType, Duration)), // Mine into the inventory of an owned planet
+        Salvage(((i32, i32), Duration)), // Loot the wreck at the position
+        ReturnHome(Speed), // Move back to the home planet
+        Idle(Duration), // Hold position for a number of blocks
+        Patrol(((i32, i32), (i32, i32), Speed)), // Move back and forth between two points
+        Follow((ShipId, u32)), // Follow another ship, keeping a trailing distance
+        Repeat((Box<Order>, u32)), // Run the inner order n times, forever if n is 0
+        Bombard((PlanetId, ItemId)), // Fire an equipped weapon at an enemy planet
+        MineAsteroid(((i32, i32), Duration)), // Mine the asteroid at the position
     }
 
-    // Directions are used to move the ship
+    // Ship stats that can be upgraded
     #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
     #[cfg_attr(
         feature = "std",
         derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
     )]
-    pub enum Direction {
-        NorthWest,
-        NorthEast,
-        East,
-        SouthEast,
-        SouthWest,
-        West,
+    pub enum ShipStat {
+        Speed,
+        EnergyCapacity,
+        RechargeRate,
+        CargoSize,
+        InventorySize,
     }
 
     /// Defines the storage of your contract.
@@ -112,7 +294,53 @@ mod rareships {
         ships: Mapping<ShipId, Ship>,
         ship_ids: Lazy<Vec<ShipId>>,
         planets: Mapping<PlanetId, Planet>,
+        tile_planet: Mapping<(i32, i32), PlanetId>,
         admin: AccountId,
+        map_bounds: (i32, i32),
+        mined_totals: Mapping<ShipId, MinedTotals>,
+        pending_bounty: Mapping<ShipId, Balance>, // Paid with orders, goes to whoever else settles the ship
+        keeper_bounties: Mapping<AccountId, Balance>, // Earned bounties that can be claimed
+        wrecks: Mapping<(i32, i32), Inventory>, // Cargo left behind by destroyed ships
+        tile_ships: Mapping<(i32, i32), TileShips>, // Ships by position
+        owner_ships: Mapping<AccountId, Vec<ShipId>>, // Ships by owner
+        collisions_enabled: bool, // Whether ships are blocked by ships on their destination tile
+        spawn_cost: Balance,      // Value that has to be transferred to spawn a ship
+        collected_fees: Balance,  // Spawn payments that haven't been withdrawn yet
+        friendly_fire: bool,      // Whether weapons damage ships of the attacker's owner
+        name_to_ship: Mapping<String, ShipId>, // Ships by name, names are unique
+        claim_expiry: Block,      // Blocks after which an unrefreshed planet claim lapses
+        ship_count: u32,          // Ships currently alive
+        planet_count: u32,        // Planets minted
+        trades: Mapping<u32, Trade>, // Pending trades by id
+        ship_trades: Mapping<ShipId, Vec<u32>>, // Pending trades by proposing ship
+        next_trade_id: u32,
+        ship_listings: Mapping<ShipId, (AccountId, Balance)>, // Ships for sale with seller and price
+        mine_energy_cost: u32,    // Energy a ship spends per block of mining
+        asteroids: Mapping<(i32, i32), Asteroid>, // Resources left in open space by position
+        total_mined: Mapping<ResourceType, u64>, // Resources mined by all ships together
+        storage_version: String,  // Code version the storage layout was written by
+    }
+
+    #[ink(event)]
+    pub struct PlanetClaimed {
+        #[ink(topic)]
+        planet_id: PlanetId,
+        #[ink(topic)]
+        owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ClaimExpired {
+        #[ink(topic)]
+        planet_id: PlanetId,
+        #[ink(topic)]
+        owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct PlanetAbandoned {
+        #[ink(topic)]
+        planet_id: PlanetId,
     }
 
     #[ink(event)]
@@ -123,6 +351,23 @@ mod rareships {
         owner: AccountId,
     }
 
+    #[ink(event)]
+    pub struct PlanetMinted {
+        #[ink(topic)]
+        planet_id: PlanetId,
+        position: (i32, i32),
+        #[ink(topic)]
+        level: PlanetLevel,
+    }
+
+    #[ink(event)]
+    pub struct ShipTeleported {
+        #[ink(topic)]
+        ship_id: ShipId,
+        from: (i32, i32),
+        to: (i32, i32),
+    }
+
     #[ink(event)]
     pub struct ShipMoved {
         #[ink(topic)]
@@ -130,6 +375,14 @@ mod rareships {
         #[ink(topic)]
         position: (i32, i32),
         energy_cost: u32,
+        wrapped: bool, // Whether the ship crossed an edge of the map
+    }
+
+    #[ink(event)]
+    pub struct ShieldRegenerated {
+        #[ink(topic)]
+        ship_id: ShipId,
+        new_shield: u32,
     }
 
     #[ink(event)]
@@ -146,6 +399,13 @@ mod rareships {
         new_energy: u32,
     }
 
+    #[ink(event)]
+    pub struct EnergyReceived {
+        #[ink(topic)]
+        ship_id: ShipId,
+        new_energy: u32,
+    }
+
     #[ink(event)]
     pub struct OrderCreated {
         #[ink(topic)]
@@ -160,6 +420,13 @@ mod rareships {
         order: Order,
     }
 
+    #[ink(event)]
+    pub struct OrderDropped {
+        #[ink(topic)]
+        ship_id: ShipId,
+        order: Order,
+    }
+
     #[ink(event)]
     pub struct OrderUpdated {
         #[ink(topic)]
@@ -168,446 +435,6241 @@ mod rareships {
     }
 
     #[ink(event)]
-    pub struct ResourceMined {
+    pub struct ResourceRefined {
         #[ink(topic)]
         ship_id: ShipId,
+        #[ink(topic)]
+        input: ResourceType,
+        #[ink(topic)]
+        output: ResourceType,
+        amount: u32,
+    }
+
+    #[ink(event)]
+    pub struct ResourcesDeposited {
+        #[ink(topic)]
         planet_id: PlanetId,
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
         resource_type: ResourceType,
-        quantity: u32,
+        amount: u32,
     }
 
     #[ink(event)]
-    pub struct DebugEvent {
+    pub struct MiningFeePaid {
         #[ink(topic)]
-        value: String,
+        planet_id: PlanetId,
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        resource_type: ResourceType,
+        amount: u32,
     }
 
-    impl Rareships {
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            Self {
-                ships: Mapping::new(),
-                ship_ids: Default::default(),
-                planets: Mapping::new(),
-                admin: Rareships::env().caller(),
-            }
-        }
+    #[ink(event)]
+    pub struct BountyPaid {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        keeper: AccountId,
+        amount: Balance,
+    }
 
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new()
-        }
+    #[ink(event)]
+    pub struct ShipHit {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        attacker: ShipId,
+        damage: u32,
+        health: u32,
+    }
 
-        #[ink(message)]
-        pub fn mint_planet(
-            &mut self,
-            planet_id: PlanetId,
-            position: (i32, i32),
-        ) -> Result<(), Error> {
-            if self.env().caller() != self.admin {
-                return Err(Error::NotAuthorized);
-            }
-            if self.planets.contains(planet_id) {
-                return Err(Error::PlanetAlreadyExists);
-            }
-            self.planets.insert(
-                planet_id,
-                &Planet::new(planet_id, PlanetLevel::Basic, position),
-            );
-            Ok(())
-        }
+    #[ink(event)]
+    pub struct FriendlyFireBlocked {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        attacker: ShipId,
+    }
 
-        #[ink(message)]
-        pub fn spawn(&mut self, ship_id: ShipId) -> Result<(), Error> {
-            if self.ships.contains(ship_id) {
-                return Err(Error::ShipAlreadyExists);
-            }
-            self.ships.insert(
-                ship_id,
-                &Ship {
-                    id: ship_id,
-                    name: String::from(""),
-                    owner: self.env().caller(),
-                    max_speed: 10000, // 10000 milli tiles per block -> 10 tiles per block
-                    max_inventory_size: 4,
-                    max_cargo_size: 4,
-                    max_energy: 1000,
-                    max_health: 100,
-                    recharge_rate: 10,
-                    position: (0, 0),
-                    energy: 1000,
-                    health: 100,
-                    inventory: Inventory::new(4),
-                    cargo: Inventory::new(32),
-                    orders: Vec::new(),
-                    last_recharge: self.env().block_number(),
-                },
-            );
-            let mut ships = self.ship_ids.get_or_default();
-            ships.push(ship_id);
-            self.ship_ids.set(&ships);
-            self.env().emit_event(ShipSpawned {
-                ship_id,
-                owner: self.env().caller(),
-            });
-            Ok(())
-        }
+    #[ink(event)]
+    pub struct ShipLeveledUp {
+        #[ink(topic)]
+        ship_id: ShipId,
+        new_level: u32,
+    }
 
-        #[ink(message)]
-        pub fn order(&mut self, ship_id: ShipId, order: Order) -> Result<(), Error> {
-            let ship_static = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
-            if ship_static.owner != self.env().caller() {
-                return Err(Error::NotShipOwner);
-            }
-            let mut ship_dynamic = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+    #[ink(event)]
+    pub struct ShipDestroyed {
+        #[ink(topic)]
+        ship_id: ShipId,
+        position: (i32, i32),
+    }
 
-            match &order {
-                Order::Move((_, speed, distance)) => {
-                    if *speed < 0 || *speed > ship_static.max_speed || *distance <= 0 {
-                        return Err(Error::InvalidOrder);
-                    }
-                }
-                Order::Mine((planet_id, resource_type, duration)) => {
-                    if *duration <= 0 {
-                        return Err(Error::InvalidOrder);
-                    }
-                    let planet = self.planets.get(*planet_id).ok_or(Error::PlanetNotFound)?;
-                    if !planet.get_resources().contains(resource_type) {
-                        return Err(Error::InvalidOrder);
-                    }
-                }
-            }
+    #[ink(event)]
+    pub struct ShipScuttled {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        owner: AccountId,
+        position: (i32, i32),
+    }
 
-            let start = match ship_dynamic.orders.is_empty() {
-                true => Some(self.env().block_number()),
-                false => None,
-            };
-            ship_dynamic.orders.push((order.clone(), start));
-            self.ships.insert(ship_id, &ship_dynamic);
-            self.env().emit_event(OrderCreated { ship_id, order });
-            Ok(())
-        }
+    #[ink(event)]
+    pub struct WreckSalvaged {
+        #[ink(topic)]
+        ship_id: ShipId,
+        position: (i32, i32),
+        items: u32,
+    }
 
-        #[ink(message)]
-        pub fn drop_order(&mut self, ship_id: ShipId, order_index: u32) -> Result<(), Error> {
-            let mut ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
-            if ship.owner != self.env().caller() {
-                return Err(Error::NotShipOwner);
-            }
-            if order_index >= ship.orders.len() as u32 {
-                return Err(Error::InvalidOrder);
-            }
-            ship.orders.remove(order_index as usize);
-            self.ships.insert(ship_id, &ship);
-            Ok(())
-        }
+    #[ink(event)]
+    pub struct MovementBlocked {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        blocked_by: ShipId,
+    }
 
-        #[ink(message)]
-        pub fn settle(&mut self, ship_id: ShipId) -> Result<(), Error> {
-            self.settle_ship(ship_id)?;
-            Ok(())
-        }
+    #[ink(event)]
+    pub struct ShipStalled {
+        #[ink(topic)]
+        ship_id: ShipId,
+        remaining_distance: i32,
+    }
 
-        #[ink(message)]
-        pub fn settle_recharge_only(&mut self, ship_id: ShipId) -> Result<(), Error> {
-            // get ship dynamic and static data
-            let mut ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+    #[ink(event)]
+    pub struct WaypointReached {
+        #[ink(topic)]
+        ship_id: ShipId,
+        position: (i32, i32),
+    }
 
-            // recharge energy
-            self.settle_recharge(&mut ship)?;
+    #[ink(event)]
+    pub struct ShipIdle {
+        #[ink(topic)]
+        ship_id: ShipId,
+    }
+
+    #[ink(event)]
+    pub struct OrderInvalidated {
+        #[ink(topic)]
+        ship_id: ShipId,
+        order: Order,
+        reason: Error,
+    }
+
+    #[ink(event)]
+    pub struct OrderExpired {
+        #[ink(topic)]
+        ship_id: ShipId,
+        order: Order,
+    }
+
+    #[ink(event)]
+    pub struct AsteroidMined {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        position: (i32, i32),
+        resource_type: ResourceType,
+        quantity: u32,
+    }
+
+    #[ink(event)]
+    pub struct AsteroidDepleted {
+        #[ink(topic)]
+        position: (i32, i32),
+    }
+
+    #[ink(event)]
+    pub struct ResourceMined {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        planet_id: PlanetId,
+        #[ink(topic)]
+        resource_type: ResourceType,
+        quantity: u32,
+    }
+
+    #[ink(event)]
+    pub struct ShipTransferred {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ShipListed {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        seller: AccountId,
+        price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ShipSold {
+        #[ink(topic)]
+        ship_id: ShipId,
+        seller: AccountId,
+        #[ink(topic)]
+        buyer: AccountId,
+        price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TradeProposed {
+        #[ink(topic)]
+        trade_id: u32,
+        #[ink(topic)]
+        ship_id: ShipId,
+        offer: (ResourceType, u32),
+        request: (ResourceType, u32),
+    }
+
+    #[ink(event)]
+    pub struct TradeAccepted {
+        #[ink(topic)]
+        trade_id: u32,
+        #[ink(topic)]
+        ship_id: ShipId,
+    }
+
+    #[ink(event)]
+    pub struct CargoTransferred {
+        #[ink(topic)]
+        from_ship: ShipId,
+        #[ink(topic)]
+        to_ship: ShipId,
+        item_id: ItemId,
+    }
+
+    #[ink(event)]
+    pub struct ItemMoved {
+        #[ink(topic)]
+        ship_id: ShipId,
+        item_id: ItemId,
+        new_item_id: ItemId,
+        to_cargo: bool,
+    }
+
+    #[ink(event)]
+    pub struct ItemEquipped {
+        #[ink(topic)]
+        ship_id: ShipId,
+        item_id: ItemId,
+    }
+
+    #[ink(event)]
+    pub struct ShipDocked {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        planet_id: PlanetId,
+    }
+
+    #[ink(event)]
+    pub struct ShipUndocked {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        planet_id: PlanetId,
+    }
+
+    #[ink(event)]
+    pub struct ItemUnequipped {
+        #[ink(topic)]
+        ship_id: ShipId,
+        item_id: ItemId,
+    }
+
+    #[ink(event)]
+    pub struct ShipUpgraded {
+        #[ink(topic)]
+        ship_id: ShipId,
+        #[ink(topic)]
+        stat: ShipStat,
+        new_value: u32,
+    }
+
+    #[ink(event)]
+    pub struct PlanetBombarded {
+        #[ink(topic)]
+        planet_id: PlanetId,
+        #[ink(topic)]
+        ship_id: ShipId,
+        damage: u32,
+        health: u32,
+    }
+
+    #[ink(event)]
+    pub struct PlanetDefended {
+        #[ink(topic)]
+        planet_id: PlanetId,
+        #[ink(topic)]
+        ship_id: ShipId,
+        damage: u32,
+    }
+
+    #[ink(event)]
+    pub struct PlanetLost {
+        #[ink(topic)]
+        planet_id: PlanetId,
+        #[ink(topic)]
+        previous_owner: AccountId,
+    }
+
+    #[cfg(feature = "debug-events")]
+    #[ink(event)]
+    pub struct DebugEvent {
+        #[ink(topic)]
+        value: String,
+    }
+
+    impl Rareships {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::new_with_map_bounds(DEFAULT_MAX_X, DEFAULT_MAX_Y)
+        }
+
+        // new_with_map_bounds creates a map of max_x * max_y tiles, ships wrap around the edges
+        #[ink(constructor)]
+        pub fn new_with_map_bounds(max_x: i32, max_y: i32) -> Self {
+            Self::new_with_config(max_x, max_y, DEFAULT_SPAWN_COST)
+        }
+
+        // new_with_config additionally sets the value that has to be paid to spawn a ship
+        #[ink(constructor)]
+        pub fn new_with_config(max_x: i32, max_y: i32, spawn_cost: Balance) -> Self {
+            assert!(max_x > 0 && max_y > 0, "map bounds must be positive");
+            Self {
+                ships: Mapping::new(),
+                ship_ids: Default::default(),
+                planets: Mapping::new(),
+                tile_planet: Mapping::new(),
+                admin: Rareships::env().caller(),
+                map_bounds: (max_x, max_y),
+                mined_totals: Mapping::new(),
+                pending_bounty: Mapping::new(),
+                keeper_bounties: Mapping::new(),
+                wrecks: Mapping::new(),
+                tile_ships: Mapping::new(),
+                owner_ships: Mapping::new(),
+                collisions_enabled: true,
+                spawn_cost,
+                collected_fees: 0,
+                friendly_fire: false,
+                name_to_ship: Mapping::new(),
+                claim_expiry: DEFAULT_CLAIM_EXPIRY,
+                ship_count: 0,
+                planet_count: 0,
+                trades: Mapping::new(),
+                next_trade_id: 0,
+                ship_listings: Mapping::new(),
+                ship_trades: Mapping::new(),
+                mine_energy_cost: DEFAULT_MINE_ENERGY_COST,
+                asteroids: Mapping::new(),
+                total_mined: Mapping::new(),
+                storage_version: String::from(VERSION),
+            }
+        }
+
+        #[ink(constructor)]
+        pub fn default() -> Self {
+            Self::new()
+        }
+
+        #[ink(message)]
+        pub fn mint_planet(
+            &mut self,
+            planet_id: PlanetId,
+            position: (i32, i32),
+            level: PlanetLevel,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            if !in_bounds(position, self.map_bounds) {
+                return Err(Error::OutOfBounds);
+            }
+            if self.planets.contains(planet_id) || self.tile_planet.contains(position) {
+                return Err(Error::PlanetAlreadyExists);
+            }
+            self.insert_planet(planet_id, position, level);
+            Ok(())
+        }
+
+        // mint_basic_planet mints a planet of the default level
+        #[ink(message)]
+        pub fn mint_basic_planet(
+            &mut self,
+            planet_id: PlanetId,
+            position: (i32, i32),
+        ) -> Result<(), Error> {
+            self.mint_planet(planet_id, position, PlanetLevel::Basic)
+        }
+
+        // mint_planets mints a batch of planets for the map setup, nothing is minted
+        // if any of them has a taken id or position or lies outside the map
+        #[ink(message)]
+        pub fn mint_planets(
+            &mut self,
+            planets: Vec<(PlanetId, (i32, i32), PlanetLevel)>,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            for (index, (planet_id, position, _)) in planets.iter().enumerate() {
+                if !in_bounds(*position, self.map_bounds) {
+                    return Err(Error::OutOfBounds);
+                }
+                let duplicate = planets[..index]
+                    .iter()
+                    .any(|(id, other, _)| id == planet_id || other == position);
+                if duplicate
+                    || self.planets.contains(planet_id)
+                    || self.tile_planet.contains(position)
+                {
+                    return Err(Error::PlanetAlreadyExists);
+                }
+            }
+            for (planet_id, position, level) in planets {
+                self.insert_planet(planet_id, position, level);
+            }
+            Ok(())
+        }
+
+        // insert_planet stores a new planet, the caller checks that id and position are free
+        fn insert_planet(&mut self, planet_id: PlanetId, position: (i32, i32), level: PlanetLevel) {
+            self.planets
+                .insert(planet_id, &Planet::new(planet_id, level.clone(), position));
+            self.tile_planet.insert(position, &planet_id);
+            self.planet_count += 1;
+            self.env().emit_event(PlanetMinted {
+                planet_id,
+                position,
+                level,
+            });
+        }
+
+        // claim_planet takes an unowned planet for the owner of the ship on it
+        #[ink(message)]
+        pub fn claim_planet(&mut self, ship_id: ShipId, planet_id: PlanetId) -> Result<(), Error> {
+            let ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let mut planet = self.load_planet(planet_id)?;
+            if planet.get_position() != ship.position {
+                return Err(Error::ShipNotAtPlanet);
+            }
+            if planet.get_owner().is_some() {
+                return Err(Error::PlanetAlreadyOwned);
+            }
+            planet.claim(ship.owner, self.env().block_number());
+            self.planets.insert(planet_id, &planet);
+            self.env().emit_event(PlanetClaimed {
+                planet_id,
+                owner: ship.owner,
+            });
+            Ok(())
+        }
+
+        // refresh_claim keeps the ownership from expiring for another claim_expiry blocks
+        #[ink(message)]
+        pub fn refresh_claim(&mut self, planet_id: PlanetId) -> Result<(), Error> {
+            let mut planet = self.load_planet(planet_id)?;
+            if planet.get_owner() != Some(self.env().caller()) {
+                return Err(Error::NotPlanetOwner);
+            }
+            planet.refresh_claim(self.env().block_number());
+            self.planets.insert(planet_id, &planet);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_claim_expiry(&mut self, claim_expiry: Block) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            self.claim_expiry = claim_expiry;
+            Ok(())
+        }
+
+        // set_code upgrades the contract to the code with the given hash,
+        // call migrate afterwards to record the new storage version
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::CodeUpgradeFailed)
+        }
+
+        // migrate records that the storage is up to date with the current code. Stored
+        // structs can't gain fields without breaking their decoding, so state added by
+        // later versions has to live in storage that defaults when missing, like a Mapping.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            self.storage_version = String::from(VERSION);
+            Ok(())
+        }
+
+        // load_planet gets a planet that is about to be used, ownership that wasn't refreshed
+        // within claim_expiry blocks is released first
+        fn load_planet(&mut self, planet_id: PlanetId) -> Result<Planet, Error> {
+            let mut planet = self.planets.get(planet_id).ok_or(Error::PlanetNotFound)?;
+            if let Some(owner) = planet.get_owner() {
+                if self.effective_owner(&planet).is_none() {
+                    planet.clear_owner();
+                    self.planets.insert(planet_id, &planet);
+                    self.env().emit_event(ClaimExpired { planet_id, owner });
+                }
+            }
+            Ok(planet)
+        }
+
+        // effective_owner is the owner of the planet unless the claim has expired,
+        // for reads that can't release the expired claim like load_planet does
+        fn effective_owner(&self, planet: &Planet) -> Option<AccountId> {
+            let expires_at = planet.get_claimed_at().saturating_add(self.claim_expiry);
+            planet
+                .get_owner()
+                .filter(|_| self.env().block_number() <= expires_at)
+        }
+
+        // abandon_planet gives up the planet so it can be claimed again.
+        // The planet's inventory is kept and goes to the next owner.
+        #[ink(message)]
+        pub fn abandon_planet(&mut self, planet_id: PlanetId) -> Result<(), Error> {
+            let mut planet = self.load_planet(planet_id)?;
+            if planet.get_owner() != Some(self.env().caller()) {
+                return Err(Error::NotPlanetOwner);
+            }
+            planet.clear_owner();
+            self.planets.insert(planet_id, &planet);
+            self.env().emit_event(PlanetAbandoned { planet_id });
+            Ok(())
+        }
+
+        // set_mining_rate lets the admin balance the economy of a minted planet
+        #[ink(message)]
+        pub fn set_mining_rate(
+            &mut self,
+            planet_id: PlanetId,
+            resource_type: ResourceType,
+            rate: u32,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            let mut planet = self.planets.get(planet_id).ok_or(Error::PlanetNotFound)?;
+            if !planet.set_mining_rate(&resource_type, rate) {
+                return Err(Error::ResourceNotFound);
+            }
+            self.planets.insert(planet_id, &planet);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_spawn_cost(&mut self, spawn_cost: Balance) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            self.spawn_cost = spawn_cost;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_spawn_cost(&self) -> Balance {
+            self.spawn_cost
+        }
+
+        #[ink(message)]
+        pub fn set_mine_energy_cost(&mut self, mine_energy_cost: u32) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            self.mine_energy_cost = mine_energy_cost;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_mine_energy_cost(&self) -> u32 {
+            self.mine_energy_cost
+        }
+
+        #[ink(message)]
+        pub fn get_collected_fees(&self) -> Balance {
+            self.collected_fees
+        }
+
+        // withdraw_fees transfers the collected spawn payments to the admin
+        #[ink(message)]
+        pub fn withdraw_fees(&mut self) -> Result<Balance, Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            let amount = self.collected_fees;
+            if self.env().transfer(self.admin, amount).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            self.collected_fees = 0;
+            Ok(amount)
+        }
+
+        // spawn creates a new ship for the caller, at least spawn_cost has to be transferred
+        #[ink(message, payable)]
+        pub fn spawn(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            if self.ships.contains(ship_id) {
+                return Err(Error::ShipAlreadyExists);
+            }
+            let payment = self.env().transferred_value();
+            if payment < self.spawn_cost {
+                return Err(Error::InsufficientPayment);
+            }
+            let owner = self.env().caller();
+            let mut owner_ships = self.owner_ships.get(owner).unwrap_or_default();
+            if owner_ships.len() >= MAX_SHIPS_PER_ACCOUNT {
+                return Err(Error::ShipLimitReached);
+            }
+            owner_ships.push(ship_id);
+            self.owner_ships.insert(owner, &owner_ships);
+            self.ships.insert(
+                ship_id,
+                &Ship {
+                    id: ship_id,
+                    name: String::from(""),
+                    owner,
+                    max_speed: 10000, // 10000 milli tiles per block -> 10 tiles per block
+                    max_inventory_size: 4,
+                    max_cargo_size: 32,
+                    max_energy: 1000,
+                    max_health: 100,
+                    recharge_rate: 10,
+                    max_shield: DEFAULT_MAX_SHIELD,
+                    shield_regen_rate: DEFAULT_SHIELD_REGEN_RATE,
+                    position: (0, 0),
+                    energy: 1000,
+                    health: 100,
+                    shield: 0,
+                    inventory: Inventory::new(4),
+                    cargo: Inventory::new(32),
+                    orders: Vec::new(),
+                    last_recharge: self.env().block_number(),
+                    last_shield_regen: self.env().block_number(),
+                    move_progress_millis: 0,
+                    equipped: Vec::new(),
+                    docked_at: None,
+                    xp: 0,
+                    level: 1,
+                    home_planet: None,
+                },
+            );
+            let mut ships = self.ship_ids.get_or_default();
+            ships.push(ship_id);
+            self.ship_ids.set(&ships);
+            self.ship_count += 1;
+            self.add_tile_ship((0, 0), ship_id);
+            self.collected_fees += payment;
+            self.env().emit_event(ShipSpawned {
+                ship_id,
+                owner,
+            });
+            Ok(())
+        }
+
+        // order queues an order, start_at optionally delays its start to a future block
+        // the transferred value is added to the ship's settlement bounty
+        #[ink(message, payable)]
+        pub fn order(
+            &mut self,
+            ship_id: ShipId,
+            order: Order,
+            start_at: Option<Block>,
+        ) -> Result<(), Error> {
+            self.add_order(ship_id, order, start_at, None)
+        }
+
+        // order_with_deadline adds an order that is dropped if it isn't completed by deadline
+        #[ink(message, payable)]
+        pub fn order_with_deadline(
+            &mut self,
+            ship_id: ShipId,
+            order: Order,
+            deadline: Block,
+        ) -> Result<(), Error> {
+            if deadline < self.env().block_number() {
+                return Err(Error::InvalidOrder);
+            }
+            self.add_order(ship_id, order, None, Some(deadline))
+        }
+
+        fn add_order(
+            &mut self,
+            ship_id: ShipId,
+            order: Order,
+            start_at: Option<Block>,
+            deadline: Option<Block>,
+        ) -> Result<(), Error> {
+            let ship = self.prepare_order(ship_id, order.clone(), start_at, deadline)?;
+            self.ships.insert(ship_id, &ship);
+            self.add_bounty(ship_id);
+            self.env().emit_event(OrderCreated { ship_id, order });
+            Ok(())
+        }
+
+        // validate_order runs the checks of order without queueing anything
+        #[ink(message)]
+        pub fn validate_order(&self, ship_id: ShipId, order: Order) -> Result<(), Error> {
+            self.prepare_order(ship_id, order, None, None).map(|_| ())
+        }
+
+        // prepare_order checks the order and returns the ship with the order queued, the
+        // ship isn't stored
+        fn prepare_order(
+            &self,
+            ship_id: ShipId,
+            order: Order,
+            start_at: Option<Block>,
+            deadline: Option<Block>,
+        ) -> Result<Ship, Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            self.check_order(&ship, &order)?;
+            self.push_order(&mut ship, order, start_at, deadline)?;
+            Ok(ship)
+        }
+
+        // submit_orders queues several orders at once. All orders are validated first,
+        // if any of them is invalid none of them is queued.
+        #[ink(message, payable)]
+        pub fn submit_orders(&mut self, ship_id: ShipId, orders: Vec<Order>) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            // later orders are checked against the queue including the earlier ones
+            for order in orders.iter() {
+                self.check_order(&ship, order).map_err(|_| Error::InvalidOrder)?;
+                self.push_order(&mut ship, order.clone(), None, None)?;
+            }
+            self.ships.insert(ship_id, &ship);
+            self.add_bounty(ship_id);
+            for order in orders {
+                self.env().emit_event(OrderCreated { ship_id, order });
+            }
+            Ok(())
+        }
+
+        // push_order appends an already checked order to the queue of the ship
+        fn push_order(
+            &self,
+            ship: &mut Ship,
+            order: Order,
+            start_at: Option<Block>,
+            deadline: Option<Block>,
+        ) -> Result<(), Error> {
+            // a repeat needs a second slot for the run of its inner order, queued repeats too
+            let slots = |order: &Order| match order {
+                Order::Repeat(_) => 2,
+                _ => 1,
+            };
+            let used: usize = ship.orders.iter().map(|(order, _, _)| slots(order)).sum();
+            if used + slots(&order) > MAX_ORDERS {
+                return Err(Error::TooManyOrders);
+            }
+
+            // queued orders keep their scheduled start until they reach the front
+            let block = self.env().block_number();
+            let start = match ship.orders.is_empty() {
+                true => Some(start_at.map_or(block, |start_at| start_at.max(block))),
+                false => start_at,
+            };
+            ship.orders.push((order, start, deadline));
+            Ok(())
+        }
+
+        // check_order validates an order for the ship without changing any state
+        fn check_order(&self, ship: &Ship, order: &Order) -> Result<(), Error> {
+            if ship.docked_at.is_some() && is_movement(order) {
+                return Err(Error::InvalidOrder);
+            }
+            match order {
+                Order::Move((_, speed, distance)) => {
+                    if *speed < 0 || *speed > ship.max_speed || *distance <= 0 {
+                        return Err(Error::InvalidOrder);
+                    }
+                }
+                Order::Mine((planet_id, resource_type, duration))
+                | Order::MineAndDeposit((planet_id, resource_type, duration)) => {
+                    if *duration == 0 {
+                        return Err(Error::InvalidOrder);
+                    }
+                    self.check_mine_order(ship, *planet_id, Some(resource_type))?;
+                }
+                Order::MineUntilFull((planet_id, resource_type)) => {
+                    self.check_mine_order(ship, *planet_id, Some(resource_type))?;
+                }
+                Order::MineAll((planet_id, duration)) => {
+                    if *duration == 0 {
+                        return Err(Error::InvalidOrder);
+                    }
+                    self.check_mine_order(ship, *planet_id, None)?;
+                }
+                Order::Idle(duration) => {
+                    if *duration == 0 {
+                        return Err(Error::InvalidOrder);
+                    }
+                }
+                Order::Salvage((position, duration)) => {
+                    if *duration == 0 {
+                        return Err(Error::InvalidOrder);
+                    }
+                    if !self.wrecks.contains(position) {
+                        return Err(Error::WreckNotFound);
+                    }
+                }
+                Order::MineAsteroid((position, duration)) => {
+                    if *duration == 0 {
+                        return Err(Error::InvalidOrder);
+                    }
+                    if !self.asteroids.contains(position) {
+                        return Err(Error::AsteroidNotFound);
+                    }
+                }
+                Order::Patrol((waypoint, other, speed)) => {
+                    if *speed <= 0
+                        || *speed > ship.max_speed
+                        || waypoint == other
+                        || !in_bounds(*waypoint, self.map_bounds)
+                        || !in_bounds(*other, self.map_bounds)
+                    {
+                        return Err(Error::InvalidOrder);
+                    }
+                }
+                Order::MoveBy(((dq, dr, ds), speed)) => {
+                    if *speed <= 0
+                        || *speed > ship.max_speed
+                        || dq + dr + ds != 0
+                        || (*dq, *dr, *ds) == (0, 0, 0)
+                    {
+                        return Err(Error::InvalidOrder);
+                    }
+                }
+                Order::ReturnHome(speed) => {
+                    if *speed <= 0 || *speed > ship.max_speed || ship.home_planet.is_none() {
+                        return Err(Error::InvalidOrder);
+                    }
+                }
+                Order::Follow((target_id, _)) => {
+                    if *target_id == ship.id {
+                        return Err(Error::InvalidOrder);
+                    }
+                    if !self.ships.contains(target_id) {
+                        return Err(Error::ShipNotFound);
+                    }
+                }
+                Order::Bombard((planet_id, weapon_id)) => {
+                    let planet = self.planets.get(*planet_id).ok_or(Error::PlanetNotFound)?;
+                    // only planets of other players can be bombarded
+                    if self
+                        .effective_owner(&planet)
+                        .is_none_or(|owner| owner == ship.owner)
+                    {
+                        return Err(Error::InvalidOrder);
+                    }
+                    equipped_weapon(ship, *weapon_id)?;
+                }
+                Order::Repeat((inner, _)) => {
+                    if matches!(**inner, Order::Repeat(_)) {
+                        return Err(Error::InvalidOrder);
+                    }
+                    self.check_order(ship, inner)?;
+                }
+            }
+            Ok(())
+        }
+
+        // check_mine_order checks a mine order, without a resource type all resources are mined
+        fn check_mine_order(
+            &self,
+            ship: &Ship,
+            planet_id: PlanetId,
+            resource_type: Option<&ResourceType>,
+        ) -> Result<(), Error> {
+            let planet = self.planets.get(planet_id).ok_or(Error::PlanetNotFound)?;
+            if resource_type.is_some_and(|rt| !planet.get_resources().contains(rt)) {
+                return Err(Error::InvalidOrder);
+            }
+            // the ship must already be in range of the planet, unless a queued move can bring it there
+            let will_move = ship
+                .orders
+                .iter()
+                .any(|(order, _, _)| matches!(order, Order::Move(_) | Order::MoveBy(_)));
+            if hex_distance(planet.get_position(), ship.position) > MINING_RANGE && !will_move {
+                return Err(Error::ShipNotAtPlanet);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn drop_order(&mut self, ship_id: ShipId, order_index: u32) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            if order_index >= ship.orders.len() as u32 {
+                return Err(Error::InvalidOrder);
+            }
+            let block = self.env().block_number();
+            if order_index == 0 {
+                self.settle_partial_mining(&mut ship)?;
+            }
+            let (order, _, _) = ship.orders.remove(order_index as usize);
+            if order_index == 0 {
+                start_next_order(&mut ship, block);
+            }
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(OrderDropped { ship_id, order });
+            Ok(())
+        }
+
+        // settle_partial_mining credits the blocks an active mining order has run so far
+        fn settle_partial_mining(&mut self, ship: &mut Ship) -> Result<(), Error> {
+            let block = self.env().block_number();
+            let (planet_id, resource_type, duration, start, deposit) = match ship.orders.first() {
+                Some((Order::Mine((planet_id, resource_type, duration)), Some(start), _)) => {
+                    (*planet_id, resource_type.clone(), *duration, *start, false)
+                }
+                Some((Order::MineAndDeposit((planet_id, resource_type, duration)), Some(start), _)) => {
+                    (*planet_id, resource_type.clone(), *duration, *start, true)
+                }
+                _ => return Ok(()),
+            };
+            if start >= block {
+                return Ok(());
+            }
+            self.settle_recharge(ship)?;
+            let elapsed = (block - start).min(duration);
+            // a ship that can't take the partial yield just drops the order
+            let _ = self.extract_resources(ship, planet_id, resource_type, elapsed, deposit);
+            Ok(())
+        }
+
+        // transfer_cargo moves a cargo item from one ship to another ship on the same tile
+        #[ink(message)]
+        pub fn transfer_cargo(
+            &mut self,
+            from_ship: ShipId,
+            to_ship: ShipId,
+            item_id: ItemId,
+        ) -> Result<(), Error> {
+            if from_ship == to_ship {
+                return Err(Error::InvalidOrder);
+            }
+            let mut from = self.load_ship(from_ship)?;
+            if from.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let mut to = self.load_ship(to_ship)?;
+            if from.position != to.position {
+                return Err(Error::ShipsNotColocated);
+            }
+
+            let item = from.cargo.remove_item(item_id).ok_or(Error::ItemNotFound)?;
+            to.cargo
+                .add_item(item)
+                .map_err(|_| Error::NotEnoughCargoSpace)?;
+            self.ships.insert(from_ship, &from);
+            self.ships.insert(to_ship, &to);
+            self.env().emit_event(CargoTransferred {
+                from_ship,
+                to_ship,
+                item_id,
+            });
+            Ok(())
+        }
+
+        // scuttle_ship destroys the caller's ship, its cargo is left behind as a wreck
+        #[ink(message)]
+        pub fn scuttle_ship(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            self.settle_ship(ship_id)?;
+            let ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            self.destroy_ship(ship_id)?;
+            self.env().emit_event(ShipScuttled {
+                ship_id,
+                owner: ship.owner,
+                position: ship.position,
+            });
+            Ok(())
+        }
+
+        // transfer_ship gives the ship to another account
+        #[ink(message)]
+        pub fn transfer_ship(&mut self, ship_id: ShipId, to: AccountId) -> Result<(), Error> {
+            let ship = self.load_ship(ship_id)?;
+            let from = self.env().caller();
+            if ship.owner != from {
+                return Err(Error::NotShipOwner);
+            }
+            self.change_owner(ship, to)?;
+            self.env().emit_event(ShipTransferred { ship_id, from, to });
+            Ok(())
+        }
+
+        // list_ship_for_sale offers the ship to anyone paying price, listing again changes the price
+        #[ink(message)]
+        pub fn list_ship_for_sale(&mut self, ship_id: ShipId, price: Balance) -> Result<(), Error> {
+            let ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+            let seller = self.env().caller();
+            if ship.owner != seller {
+                return Err(Error::NotShipOwner);
+            }
+            self.ship_listings.insert(ship_id, &(seller, price));
+            self.env().emit_event(ShipListed {
+                ship_id,
+                seller,
+                price,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_listing(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            let (seller, _) = self.ship_listings.get(ship_id).ok_or(Error::NotForSale)?;
+            if seller != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            self.ship_listings.remove(ship_id);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_listing(&self, ship_id: ShipId) -> Option<(AccountId, Balance)> {
+            self.ship_listings.get(ship_id)
+        }
+
+        // buy_ship pays the price to the seller and hands the ship to the caller,
+        // anything paid above the price is refunded
+        #[ink(message, payable)]
+        pub fn buy_ship(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            let (seller, price) = self.ship_listings.get(ship_id).ok_or(Error::NotForSale)?;
+            let ship = self.load_ship(ship_id)?;
+            if ship.owner != seller {
+                // the ship changed hands since it was listed
+                return Err(Error::NotForSale);
+            }
+            let buyer = self.env().caller();
+            if buyer == seller {
+                return Err(Error::InvalidOrder);
+            }
+            let payment = self.env().transferred_value();
+            if payment < price {
+                return Err(Error::InsufficientPayment);
+            }
+            self.change_owner(ship, buyer)?;
+            if self.env().transfer(seller, price).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            if payment > price && self.env().transfer(buyer, payment - price).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(ShipSold {
+                ship_id,
+                seller,
+                buyer,
+                price,
+            });
+            Ok(())
+        }
+
+        // change_owner moves the ship into the ship index of its new owner
+        fn change_owner(&mut self, mut ship: Ship, to: AccountId) -> Result<(), Error> {
+            let mut to_ships = self.owner_ships.get(to).unwrap_or_default();
+            if to_ships.len() >= MAX_SHIPS_PER_ACCOUNT {
+                return Err(Error::ShipLimitReached);
+            }
+            to_ships.push(ship.id);
+            self.owner_ships.insert(to, &to_ships);
+            let mut from_ships = self.owner_ships.get(ship.owner).unwrap_or_default();
+            from_ships.retain(|id| *id != ship.id);
+            self.owner_ships.insert(ship.owner, &from_ships);
+            ship.owner = to;
+            self.ships.insert(ship.id, &ship);
+            // a listing never outlives the owner who made it
+            self.ship_listings.remove(ship.id);
+            Ok(())
+        }
+
+        // propose_trade locks the offered resources of a docked ship until another player
+        // accepts the trade with the requested resources
+        #[ink(message)]
+        pub fn propose_trade(
+            &mut self,
+            ship_id: ShipId,
+            offer: (ResourceType, u32),
+            request: (ResourceType, u32),
+        ) -> Result<u32, Error> {
+            if offer.1 == 0 || request.1 == 0 {
+                return Err(Error::InvalidOrder);
+            }
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            if ship.docked_at.is_none() {
+                return Err(Error::InvalidOrder);
+            }
+            ship.cargo.consume_resource(&offer.0, offer.1)?;
+            let trade_id = self.next_trade_id;
+            self.next_trade_id += 1;
+            self.ships.insert(ship_id, &ship);
+            self.trades.insert(
+                trade_id,
+                &Trade {
+                    ship_id,
+                    offer: offer.clone(),
+                    request: request.clone(),
+                },
+            );
+            let mut ship_trades = self.ship_trades.get(ship_id).unwrap_or_default();
+            ship_trades.push(trade_id);
+            self.ship_trades.insert(ship_id, &ship_trades);
+            self.env().emit_event(TradeProposed {
+                trade_id,
+                ship_id,
+                offer,
+                request,
+            });
+            Ok(trade_id)
+        }
+
+        // accept_trade swaps the resources between the proposing ship and a ship on the same tile,
+        // nothing changes if either cargo can't take its side
+        #[ink(message)]
+        pub fn accept_trade(&mut self, trade_id: u32, ship_id: ShipId) -> Result<(), Error> {
+            let trade = self.trades.get(trade_id).ok_or(Error::TradeNotFound)?;
+            if trade.ship_id == ship_id {
+                return Err(Error::InvalidOrder);
+            }
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let mut proposer = self.load_ship(trade.ship_id)?;
+            if proposer.position != ship.position {
+                return Err(Error::ShipsNotColocated);
+            }
+
+            let (request_type, request_quantity) = trade.request;
+            ship.cargo.consume_resource(&request_type, request_quantity)?;
+            let (offer_type, offer_quantity) = trade.offer;
+            ship.cargo
+                .add_item(Item::Resource(Resource::new(offer_type, offer_quantity)))
+                .map_err(|_| Error::NotEnoughCargoSpace)?;
+            proposer
+                .cargo
+                .add_item(Item::Resource(Resource::new(request_type, request_quantity)))
+                .map_err(|_| Error::NotEnoughCargoSpace)?;
+
+            self.remove_trade(trade_id, trade.ship_id);
+            self.ships.insert(ship_id, &ship);
+            self.ships.insert(trade.ship_id, &proposer);
+            self.env().emit_event(TradeAccepted { trade_id, ship_id });
+            Ok(())
+        }
+
+        // cancel_trade gives the locked offer back to the proposing ship
+        #[ink(message)]
+        pub fn cancel_trade(&mut self, trade_id: u32) -> Result<(), Error> {
+            let trade = self.trades.get(trade_id).ok_or(Error::TradeNotFound)?;
+            let mut ship = self.load_ship(trade.ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let (offer_type, offer_quantity) = trade.offer;
+            ship.cargo
+                .add_item(Item::Resource(Resource::new(offer_type, offer_quantity)))
+                .map_err(|_| Error::NotEnoughCargoSpace)?;
+            self.remove_trade(trade_id, trade.ship_id);
+            self.ships.insert(trade.ship_id, &ship);
+            Ok(())
+        }
+
+        fn remove_trade(&mut self, trade_id: u32, ship_id: ShipId) {
+            self.trades.remove(trade_id);
+            let mut ship_trades = self.ship_trades.get(ship_id).unwrap_or_default();
+            ship_trades.retain(|id| *id != trade_id);
+            if ship_trades.is_empty() {
+                self.ship_trades.remove(ship_id);
+            } else {
+                self.ship_trades.insert(ship_id, &ship_trades);
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_trade(&self, trade_id: u32) -> Option<Trade> {
+            self.trades.get(trade_id)
+        }
+
+        // transfer_energy moves energy from one ship to another ship on the same tile.
+        // The amount is clamped to what the sender has and what the receiver can hold.
+        #[ink(message)]
+        pub fn transfer_energy(
+            &mut self,
+            from_ship: ShipId,
+            to_ship: ShipId,
+            amount: u32,
+        ) -> Result<(), Error> {
+            if from_ship == to_ship || amount == 0 {
+                return Err(Error::InvalidOrder);
+            }
+            let mut from = self.load_ship(from_ship)?;
+            if from.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let mut to = self.load_ship(to_ship)?;
+            if from.position != to.position {
+                return Err(Error::ShipsNotColocated);
+            }
+
+            // work with up to date energy levels
+            self.settle_recharge(&mut from)?;
+            self.settle_recharge(&mut to)?;
+
+            let amount = amount
+                .min(from.energy)
+                .min(to.max_energy.saturating_sub(to.energy));
+            from.energy -= amount;
+            to.energy += amount;
+            self.ships.insert(from_ship, &from);
+            self.ships.insert(to_ship, &to);
+            self.env().emit_event(EnergyUsed {
+                ship_id: from_ship,
+                new_energy: from.energy,
+            });
+            self.env().emit_event(EnergyReceived {
+                ship_id: to_ship,
+                new_energy: to.energy,
+            });
+            Ok(())
+        }
+
+        // move_item moves an item between the ship's inventory and cargo.
+        // Equipped items have to be unequipped first.
+        #[ink(message)]
+        pub fn move_item(
+            &mut self,
+            ship_id: ShipId,
+            item_id: ItemId,
+            to_cargo: bool,
+        ) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let new_item_id = if to_cargo {
+                if ship.equipped.contains(&item_id) {
+                    return Err(Error::InvalidOrder);
+                }
+                let item = ship.inventory.remove_item(item_id).ok_or(Error::ItemNotFound)?;
+                ship.cargo
+                    .add_item(item)
+                    .map_err(|_| Error::NotEnoughCargoSpace)?
+            } else {
+                let item = ship.cargo.remove_item(item_id).ok_or(Error::ItemNotFound)?;
+                ship.inventory.add_item(item)?
+            };
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(ItemMoved {
+                ship_id,
+                item_id,
+                new_item_id,
+                to_cargo,
+            });
+            Ok(())
+        }
+
+        // targets_in_range returns the other ships within range of the equipped weapon,
+        // nothing if the ship has no such weapon equipped. The weapon is picked by its item id
+        // like in fire_weapon, so a query and the shot that follows it name the same weapon.
+        #[ink(message)]
+        pub fn targets_in_range(&self, ship_id: ShipId, weapon_id: ItemId) -> Vec<ShipId> {
+            let ship = match self.ships.get(ship_id) {
+                Some(ship) => ship,
+                None => return Vec::new(),
+            };
+            let weapon = match equipped_weapon(&ship, weapon_id) {
+                Ok(weapon) => weapon,
+                Err(_) => return Vec::new(),
+            };
+            let mut targets: Vec<ShipId> = self
+                .ships_in_range(ship.position, weapon.range() as i32)
+                .into_iter()
+                .filter(|target_id| *target_id != ship_id)
+                .collect();
+            targets.sort();
+            targets
+        }
+
+        // fire_weapon shoots an equipped weapon at the target tile. Every other ship within
+        // the blast radius of the target takes the damage, reduced by its equipped armor.
+        // Ships without health left are destroyed.
+        #[ink(message)]
+        pub fn fire_weapon(
+            &mut self,
+            ship_id: ShipId,
+            weapon_id: ItemId,
+            target: (i32, i32),
+        ) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let weapon = equipped_weapon(&ship, weapon_id)?;
+            // the range wraps around the map edges like targets_in_range does
+            if !tiles_in_range(ship.position, weapon.range() as i32, self.map_bounds).contains(&target) {
+                return Err(Error::TargetOutOfRange);
+            }
+            let block = self.env().block_number();
+            if block < weapon.ready_at() {
+                return Err(Error::WeaponOnCooldown);
+            }
+            self.settle_recharge(&mut ship)?;
+            if ship.energy < weapon.energy_cost() {
+                return Err(Error::NotEnoughEnergy);
+            }
+            ship.energy -= weapon.energy_cost();
+            // the cooldown is kept on the weapon, so it can't be dodged by moving the weapon
+            if let Some(Item::Weapon(fired)) = ship.inventory.get_item_mut(weapon_id) {
+                fired.fired(block);
+            }
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(EnergyUsed {
+                ship_id,
+                new_energy: ship.energy,
+            });
+
+            let mut damage_dealt = 0;
+            for target_id in self.ships_in_range(target, weapon.blast_radius() as i32) {
+                if target_id == ship_id {
+                    continue;
+                }
+                let mut target_ship = self.load_ship(target_id)?;
+                if target_ship.owner == ship.owner && !self.friendly_fire {
+                    self.env().emit_event(FriendlyFireBlocked {
+                        ship_id: target_id,
+                        attacker: ship_id,
+                    });
+                    continue;
+                }
+                self.settle_shield(&mut target_ship);
+                let damage = weapon.damage().saturating_sub(armor_defense(&target_ship));
+                let damage = apply_damage(&mut target_ship, damage);
+                damage_dealt += damage;
+                self.ships.insert(target_id, &target_ship);
+                self.env().emit_event(ShipHit {
+                    ship_id: target_id,
+                    attacker: ship_id,
+                    damage,
+                    health: target_ship.health,
+                });
+                if target_ship.health == 0 {
+                    self.destroy_ship(target_id)?;
+                }
+            }
+            if damage_dealt > 0 {
+                self.award_xp(&mut ship, damage_dealt);
+                self.ships.insert(ship_id, &ship);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_friendly_fire(&mut self, enabled: bool) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            self.friendly_fire = enabled;
+            Ok(())
+        }
+
+        // set_ship_name renames the ship, names are unique and an empty name frees the old one
+        #[ink(message)]
+        pub fn set_ship_name(&mut self, ship_id: ShipId, name: String) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            if name == ship.name {
+                return Ok(());
+            }
+            if !name.is_empty() {
+                if self.name_to_ship.contains(&name) {
+                    return Err(Error::NameTaken);
+                }
+                self.name_to_ship.insert(&name, &ship_id);
+            }
+            if !ship.name.is_empty() {
+                self.name_to_ship.remove(&ship.name);
+            }
+            ship.name = name;
+            self.ships.insert(ship_id, &ship);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_ship_by_name(&self, name: String) -> Option<ShipId> {
+            self.name_to_ship.get(name)
+        }
+
+        // set_home_planet makes the owned planet the ship is docked at its home
+        #[ink(message)]
+        pub fn set_home_planet(&mut self, ship_id: ShipId, planet_id: PlanetId) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let planet = self.load_planet(planet_id)?;
+            if planet.get_owner() != Some(ship.owner) {
+                return Err(Error::NotPlanetOwner);
+            }
+            if ship.docked_at != Some(planet_id) {
+                return Err(Error::ShipNotAtPlanet);
+            }
+            ship.home_planet = Some(planet_id);
+            self.ships.insert(ship_id, &ship);
+            Ok(())
+        }
+
+        // equip_item puts a weapon or armor from the ship's inventory into use
+        #[ink(message)]
+        pub fn equip_item(&mut self, ship_id: ShipId, item_id: ItemId) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            match ship.inventory.get_item(item_id) {
+                Some(Item::Weapon(_)) | Some(Item::Armor(_)) => {}
+                Some(Item::Resource(_)) => return Err(Error::InvalidOrder),
+                None => return Err(Error::ItemNotFound),
+            }
+            if ship.equipped.contains(&item_id) {
+                return Err(Error::InvalidOrder);
+            }
+            if ship.equipped.len() >= MAX_EQUIPPED_ITEMS {
+                return Err(Error::EquipSlotsFull);
+            }
+            ship.equipped.push(item_id);
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(ItemEquipped { ship_id, item_id });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unequip_item(&mut self, ship_id: ShipId, item_id: ItemId) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let index = ship
+                .equipped
+                .iter()
+                .position(|id| *id == item_id)
+                .ok_or(Error::ItemNotFound)?;
+            ship.equipped.remove(index);
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(ItemUnequipped { ship_id, item_id });
+            Ok(())
+        }
+
+        // dock docks the ship at the planet it is on, a docked ship can't move until undocked
+        #[ink(message)]
+        pub fn dock(&mut self, ship_id: ShipId, planet_id: PlanetId) -> Result<(), Error> {
+            self.settle_ship(ship_id)?;
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let planet = self.planets.get(planet_id).ok_or(Error::PlanetNotFound)?;
+            if planet.get_position() != ship.position {
+                return Err(Error::ShipNotAtPlanet);
+            }
+            // queued movement would take the ship away from the planet
+            if ship.docked_at.is_some() || ship.orders.iter().any(|(order, _, _)| is_movement(order)) {
+                return Err(Error::InvalidOrder);
+            }
+            ship.docked_at = Some(planet_id);
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(ShipDocked { ship_id, planet_id });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn undock(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let planet_id = ship.docked_at.take().ok_or(Error::InvalidOrder)?;
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(ShipUndocked { ship_id, planet_id });
+            Ok(())
+        }
+
+        // deposit_to_planet stores a resource stack of the cargo on the owned planet the ship is docked at
+        #[ink(message)]
+        pub fn deposit_to_planet(
+            &mut self,
+            ship_id: ShipId,
+            planet_id: PlanetId,
+            item_id: ItemId,
+        ) -> Result<(), Error> {
+            let (mut ship, mut planet) = self.docked_at_owned_planet(ship_id, planet_id)?;
+            let item = ship.cargo.remove_item(item_id).ok_or(Error::ItemNotFound)?;
+            let (resource_type, amount) = match &item {
+                Item::Resource(resource) => (resource.resource_type(), resource.quantity()),
+                _ => return Err(Error::InvalidOrder),
+            };
+            planet
+                .add_item(item)
+                .map_err(|_| Error::NotEnoughInventorySpace)?;
+            self.ships.insert(ship_id, &ship);
+            self.planets.insert(planet_id, &planet);
+            self.env().emit_event(ResourcesDeposited {
+                planet_id,
+                ship_id,
+                resource_type,
+                amount,
+            });
+            Ok(())
+        }
+
+        // collect_planet_resources loads an item of the planet's inventory into the cargo of the docked ship
+        #[ink(message)]
+        pub fn collect_planet_resources(
+            &mut self,
+            ship_id: ShipId,
+            planet_id: PlanetId,
+            item_id: ItemId,
+        ) -> Result<(), Error> {
+            let (mut ship, mut planet) = self.docked_at_owned_planet(ship_id, planet_id)?;
+            let item = planet.remove_item(item_id).ok_or(Error::ItemNotFound)?;
+            ship.cargo
+                .add_item(item)
+                .map_err(|_| Error::NotEnoughCargoSpace)?;
+            self.ships.insert(ship_id, &ship);
+            self.planets.insert(planet_id, &planet);
+            Ok(())
+        }
+
+        // docked_at_owned_planet loads a ship of the caller that is docked at a planet they own
+        fn docked_at_owned_planet(
+            &mut self,
+            ship_id: ShipId,
+            planet_id: PlanetId,
+        ) -> Result<(Ship, Planet), Error> {
+            let ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            if ship.docked_at != Some(planet_id) {
+                return Err(Error::ShipNotAtPlanet);
+            }
+            let planet = self.load_planet(planet_id)?;
+            if planet.get_owner() != Some(ship.owner) {
+                return Err(Error::NotPlanetOwner);
+            }
+            Ok((ship, planet))
+        }
+
+        // refine turns amount of the input resource in the cargo into its refined type,
+        // amount has to be a multiple of the recipe's input units
+        #[ink(message)]
+        pub fn refine(
+            &mut self,
+            ship_id: ShipId,
+            input: ResourceType,
+            amount: u32,
+        ) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let (output, ratio) = refining_recipe(&input).ok_or(Error::InvalidOrder)?;
+            if amount == 0 || !amount.is_multiple_of(ratio) {
+                return Err(Error::InvalidOrder);
+            }
+            let refined = amount / ratio;
+
+            // the output has to fit once the input is taken out of the cargo
+            let mut cargo = ship.cargo.clone();
+            cargo.consume_resource(&input, amount)?;
+            let item = Item::Resource(Resource::new(output.clone(), refined));
+            if !cargo.has_space(&item) {
+                return Err(Error::NotEnoughCargoSpace);
+            }
+
+            self.settle_recharge(&mut ship)?;
+            let cost = refined * REFINE_ENERGY_PER_UNIT;
+            if ship.energy < cost {
+                return Err(Error::NotEnoughEnergy);
+            }
+            cargo.add_item(item)?;
+            ship.cargo = cargo;
+            ship.energy -= cost;
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(EnergyUsed {
+                ship_id,
+                new_energy: ship.energy,
+            });
+            self.env().emit_event(ResourceRefined {
+                ship_id,
+                input,
+                output,
+                amount: refined,
+            });
+            Ok(())
+        }
+
+        // upgrade_speed installs a better engine, paid with resources from the cargo.
+        // Since the energy per tile scales with speed / max_speed, a faster ship also
+        // moves cheaper at the same speed, while moving at the new max speed costs the same.
+        #[ink(message)]
+        pub fn upgrade_speed(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            self.upgrade_ship(ship_id, ShipStat::Speed)
+        }
+
+        // upgrade_energy_capacity raises max_energy, the new capacity has to be recharged
+        #[ink(message)]
+        pub fn upgrade_energy_capacity(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            self.upgrade_ship(ship_id, ShipStat::EnergyCapacity)
+        }
+
+        #[ink(message)]
+        pub fn upgrade_recharge_rate(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            self.upgrade_ship(ship_id, ShipStat::RechargeRate)
+        }
+
+        #[ink(message)]
+        pub fn upgrade_cargo(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            self.upgrade_ship(ship_id, ShipStat::CargoSize)
+        }
+
+        #[ink(message)]
+        pub fn upgrade_inventory(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            self.upgrade_ship(ship_id, ShipStat::InventorySize)
+        }
+
+        // split_stack splits amount off a resource stack in the cargo or inventory into a new stack
+        #[ink(message)]
+        pub fn split_stack(
+            &mut self,
+            ship_id: ShipId,
+            item_id: ItemId,
+            amount: u32,
+        ) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            if ship.cargo.get_item(item_id).is_some() {
+                ship.cargo.split_stack(item_id, amount)?;
+            } else {
+                ship.inventory.split_stack(item_id, amount)?;
+            }
+            self.ships.insert(ship_id, &ship);
+            Ok(())
+        }
+
+        // settle settles the ship. Anyone but the owner settling a ship whose orders
+        // made progress earns the bounty that was paid with the orders.
+        #[ink(message)]
+        pub fn settle(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            let ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+            self.settle_ship(ship_id)?;
+
+            let keeper = self.env().caller();
+            let progressed = self.ships.get(ship_id).is_some_and(|settled| {
+                settled.orders.len() < ship.orders.len()
+                    || settled.position != ship.position
+                    || settled.orders.first().map(|order| &order.0)
+                        != ship.orders.first().map(|order| &order.0)
+            });
+            if keeper != ship.owner && progressed {
+                self.pay_bounty(ship_id, keeper);
+            }
+            Ok(())
+        }
+
+        // claim_bounty transfers the bounties the caller earned by settling ships
+        #[ink(message)]
+        pub fn claim_bounty(&mut self) -> Result<Balance, Error> {
+            let keeper = self.env().caller();
+            let amount = self.keeper_bounties.get(keeper).unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::NoBounty);
+            }
+            self.keeper_bounties.remove(keeper);
+            if self.env().transfer(keeper, amount).is_err() {
+                self.keeper_bounties.insert(keeper, &amount);
+                return Err(Error::TransferFailed);
+            }
+            Ok(amount)
+        }
+
+        #[ink(message)]
+        pub fn get_pending_bounty(&self, ship_id: ShipId) -> Balance {
+            self.pending_bounty.get(ship_id).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_keeper_bounty(&self, keeper: AccountId) -> Balance {
+            self.keeper_bounties.get(keeper).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn settle_recharge_only(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            // get ship dynamic and static data
+            let mut ship = self.load_ship(ship_id)?;
+
+            // recharge energy
+            self.settle_recharge(&mut ship)?;
+            self.settle_shield(&mut ship);
+
+            // save updated dynamics
+            self.ships.insert(ship_id, &ship);
+
+            Ok(())
+        }
+
+        // version returns the version of the running contract code
+        #[ink(message)]
+        pub fn version(&self) -> String {
+            String::from(VERSION)
+        }
+
+        // get_storage_version returns the version the storage was last written by,
+        // a migration is due when it differs from the code version
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> String {
+            self.storage_version.clone()
+        }
+
+        #[ink(message)]
+        pub fn get_stats(&self) -> ContractStats {
+            ContractStats {
+                ship_count: self.ship_count,
+                planet_count: self.planet_count,
+                admin: self.admin,
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_ships(&self) -> Vec<ShipId> {
+            self.ship_ids.get_or_default()
+        }
+
+        // get_ship_limit is the number of ships a single account can own
+        #[ink(message)]
+        pub fn get_ship_limit(&self) -> u32 {
+            MAX_SHIPS_PER_ACCOUNT as u32
+        }
+
+        #[ink(message)]
+        pub fn get_ship(&self, ship_id: ShipId) -> Option<Ship> {
+            self.ships.get(ship_id)
+        }
+
+        // get_ship_summary is a compact alternative to get_ship for list views
+        #[ink(message)]
+        pub fn get_ship_summary(&self, ship_id: ShipId) -> Option<ShipSummary> {
+            self.ships.get(ship_id).map(|ship| ShipSummary {
+                id: ship.id(),
+                name: String::from(ship.name()),
+                owner: ship.owner(),
+                position: ship.position(),
+                energy: ship.energy(),
+                health: ship.health(),
+                order_count: ship.orders().len() as u32,
+            })
+        }
+
+        // get_ship_position is a lightweight alternative to get_ship for map polling
+        #[ink(message)]
+        pub fn get_ship_position(&self, ship_id: ShipId) -> Option<(i32, i32)> {
+            self.ships.get(ship_id).map(|ship| ship.position)
+        }
+
+        // preview_recharge returns the energy the ship would have if it was settled now
+        #[ink(message)]
+        pub fn preview_recharge(&self, ship_id: ShipId) -> Option<u32> {
+            let ship = self.load_ship(ship_id).ok()?;
+            Some(self.recharged_energy(&ship))
+        }
+
+        // preview_move_cost returns the energy a move with speed over distance tiles would take
+        #[ink(message)]
+        pub fn preview_move_cost(&self, ship_id: ShipId, speed: i32, distance: i32) -> Result<u32, Error> {
+            let ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+            if speed < 0 || speed > ship.max_speed || distance <= 0 {
+                return Err(Error::InvalidOrder);
+            }
+            // long distances saturate, no ship could afford them anyway
+            Ok((move_energy_per_tile(speed, ship.max_speed) as u32).saturating_mul(distance as u32))
+        }
+
+        // get_order_progress returns the active front order with the blocks elapsed since it
+        // started and the blocks it needs at full speed, open-ended orders need 0 blocks
+        #[ink(message)]
+        pub fn get_order_progress(&self, ship_id: ShipId) -> Option<(Order, u32, u32)> {
+            let ship = self.ships.get(ship_id)?;
+            let block = self.env().block_number();
+            let (order, start, _) = ship.orders.first()?;
+            let start = start.filter(|start| *start <= block)?;
+            let required = self.required_blocks(&ship, order).unwrap_or(0);
+            Some((order.clone(), block - start, required))
+        }
+
+        // estimate_completion returns the block the active front order completes at,
+        // assuming the ship never runs short of energy. None for open-ended orders.
+        #[ink(message)]
+        pub fn estimate_completion(&self, ship_id: ShipId) -> Option<Block> {
+            let ship = self.ships.get(ship_id)?;
+            let block = self.env().block_number();
+            let (order, start, _) = ship.orders.first()?;
+            let start = start.filter(|start| *start <= block)?;
+            let required = self.required_blocks(&ship, order)?;
+            Some(start.saturating_add(required))
+        }
+
+        // required_blocks returns how many blocks the front order takes at full speed from
+        // its start, None if the order has no fixed end. Moves already made progress
+        // towards the next tile, the start is reset on every settlement.
+        fn required_blocks(&self, ship: &Ship, order: &Order) -> Option<u32> {
+            // a ship without speed never arrives, long moves saturate
+            let blocks_for = |tiles: i32, speed: i32| match speed {
+                speed if speed <= 0 => None,
+                speed => {
+                    let millis = (tiles as i64 * 1000 - ship.move_progress_millis as i64).max(0);
+                    let speed = speed as i64;
+                    Some(u32::try_from((millis + speed - 1) / speed).unwrap_or(u32::MAX))
+                }
+            };
+            match order {
+                Order::Move((_, speed, distance)) => blocks_for(*distance, *speed),
+                Order::MoveBy((delta, speed)) => blocks_for(cube_length(*delta), *speed),
+                Order::ReturnHome(speed) => {
+                    let home = self.planets.get(ship.home_planet?)?;
+                    blocks_for(hex_distance(ship.position, home.get_position()), *speed)
+                }
+                Order::Mine((_, _, duration))
+                | Order::MineAndDeposit((_, _, duration))
+                | Order::MineAll((_, duration))
+                | Order::Salvage((_, duration))
+                | Order::MineAsteroid((_, duration))
+                | Order::Idle(duration) => Some(*duration),
+                _ => None,
+            }
+        }
+
+        // get_ship_position_millis returns the position in milli-tiles as of the last settlement,
+        // including the progress towards the next tile of the active move
+        #[ink(message)]
+        pub fn get_ship_position_millis(&self, ship_id: ShipId) -> Option<(i32, i32)> {
+            let ship = self.ships.get(ship_id)?;
+            let (x, y) = ship.position;
+            let (dx, dy) = match self.next_step(&ship) {
+                Some(next) => wrapped_offset(ship.position, next, self.map_bounds),
+                None => (0, 0),
+            };
+            let progress = ship.move_progress_millis;
+            Some((x * 1000 + dx * progress, y * 1000 + dy * progress))
+        }
+
+        // next_step returns the tile the active movement order of the ship heads to next
+        fn next_step(&self, ship: &Ship) -> Option<(i32, i32)> {
+            let target = match &ship.orders.first()?.0 {
+                Order::Move((direction, _, _)) => {
+                    return Some(move_in_direction(ship.position, direction, 1, self.map_bounds))
+                }
+                Order::MoveBy((delta, _)) => {
+                    return Some(move_in_direction(
+                        ship.position,
+                        &direction_along(*delta),
+                        1,
+                        self.map_bounds,
+                    ))
+                }
+                Order::Patrol((waypoint, _, _)) => *waypoint,
+                Order::Follow((target_id, _)) => self.ships.get(target_id)?.position,
+                Order::ReturnHome(_) => self.planets.get(ship.home_planet?)?.get_position(),
+                _ => return None,
+            };
+            Some(step_towards(ship.position, target, self.map_bounds))
+        }
+
+        // get_ship_energy returns the energy as of the last settlement
+        #[ink(message)]
+        pub fn get_ship_energy(&self, ship_id: ShipId) -> Option<u32> {
+            self.ships.get(ship_id).map(|ship| ship.energy)
+        }
+
+        // get_ship_order_count returns the number of queued orders, 0 for unknown ships
+        #[ink(message)]
+        pub fn get_ship_order_count(&self, ship_id: ShipId) -> u32 {
+            self.ships
+                .get(ship_id)
+                .map_or(0, |ship| ship.orders.len() as u32)
+        }
+
+        // settle_and_get_ship settles the ship and returns its fresh state
+        #[ink(message)]
+        pub fn settle_and_get_ship(&mut self, ship_id: ShipId) -> Result<Ship, Error> {
+            self.settle_ship(ship_id)?;
+            self.ships.get(ship_id).ok_or(Error::ShipNotFound)
+        }
+
+        // get_resource_metadata returns the (value, weight) of a single unit of the resource
+        #[ink(message)]
+        pub fn get_resource_metadata(&self, resource_type: ResourceType) -> (u32, u32) {
+            resource_metadata(&resource_type)
+        }
+
+        // get_all_resource_types lists every resource type, so clients don't hardcode them
+        #[ink(message)]
+        pub fn get_all_resource_types(&self) -> Vec<ResourceType> {
+            ALL_RESOURCE_TYPES.to_vec()
+        }
+
+        #[ink(message)]
+        pub fn get_neighbors(&self, position: (i32, i32)) -> Vec<(i32, i32)> {
+            hex_neighbors(position, self.map_bounds).to_vec()
+        }
+
+        #[ink(message)]
+        pub fn get_direction_to(&self, from: (i32, i32), to: (i32, i32)) -> Direction {
+            direction_to(from, to, self.map_bounds)
+        }
+
+        #[ink(message)]
+        pub fn get_map_bounds(&self) -> (i32, i32) {
+            self.map_bounds
+        }
+
+        #[ink(message)]
+        pub fn get_current_block(&self) -> Block {
+            self.env().block_number()
+        }
+
+        #[ink(message)]
+        pub fn get_inventory(&self, ship_id: ShipId) -> Option<Vec<Item>> {
+            self.ships.get(ship_id).map(|ship| ship.inventory().items())
+        }
+
+        #[ink(message)]
+        pub fn get_cargo(&self, ship_id: ShipId) -> Option<Vec<Item>> {
+            self.ships.get(ship_id).map(|ship| ship.cargo().items())
+        }
+
+        // get_ship_resource_count returns how much of the resource the ship carries in its cargo
+        #[ink(message)]
+        pub fn get_ship_resource_count(&self, ship_id: ShipId, rt: ResourceType) -> u32 {
+            self.ships
+                .get(ship_id)
+                .map_or(0, |ship| ship.cargo().count_resource(&rt))
+        }
+
+        // ship_net_worth sums up the value of inventory and cargo, it's 0 for unknown ships
+        #[ink(message)]
+        pub fn ship_net_worth(&self, ship_id: ShipId) -> u32 {
+            self.ships
+                .get(ship_id)
+                .map_or(0, |ship| ship.inventory.value() + ship.cargo.value())
+        }
+
+        // get_leaderboard returns up to limit ships with the highest net worth, richest first.
+        // It reads every ship, so it is meant for off-chain queries and the limit is capped
+        // at MAX_LEADERBOARD_LIMIT.
+        #[ink(message)]
+        pub fn get_leaderboard(&self, limit: u32) -> Vec<(ShipId, u32)> {
+            let mut board: Vec<(ShipId, u32)> = self
+                .ship_ids
+                .get_or_default()
+                .into_iter()
+                .map(|ship_id| (ship_id, self.ship_net_worth(ship_id)))
+                .collect();
+            // ties go to the older ship id
+            board.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            board.truncate(limit.min(MAX_LEADERBOARD_LIMIT) as usize);
+            board
+        }
+
+        // get_total_mined returns how much of the resource all ships have mined so far
+        #[ink(message)]
+        pub fn get_total_mined(&self, rt: ResourceType) -> u64 {
+            self.total_mined.get(rt).unwrap_or_default()
+        }
+
+        // get_mined_totals returns how much of each resource the ship has mined so far
+        #[ink(message)]
+        pub fn get_mined_totals(&self, ship_id: ShipId) -> MinedTotals {
+            self.mined_totals.get(ship_id).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_planet(&self, planet_id: PlanetId) -> Option<Planet> {
+            self.planets.get(planet_id)
+        }
+
+        // get_planet_inventory returns the stockpile of the planet, None for unknown planets
+        #[ink(message)]
+        pub fn get_planet_inventory(&self, planet_id: PlanetId) -> Option<Vec<Item>> {
+            self.planets.get(planet_id).map(|planet| planet.get_inventory().items())
+        }
+
+        // get_planet_owner returns who controls the planet, Ok(None) for unclaimed planets
+        #[ink(message)]
+        pub fn get_planet_owner(&self, planet_id: PlanetId) -> Result<Option<AccountId>, Error> {
+            let planet = self.planets.get(planet_id).ok_or(Error::PlanetNotFound)?;
+            Ok(self.effective_owner(&planet))
+        }
+
+        // get_planet_yields returns the mining rate of each resource the planet offers
+        #[ink(message)]
+        pub fn get_planet_yields(&self, planet_id: PlanetId) -> Option<Vec<(ResourceType, u32)>> {
+            self.planets.get(planet_id).map(|planet| {
+                planet
+                    .get_resources()
+                    .into_iter()
+                    .zip(planet.get_mining_rates())
+                    .collect()
+            })
+        }
+
+        pub fn settle_ship(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            // get ship dynamic and static data
+            let mut ship = self.load_ship(ship_id)?;
+            let position = ship.position;
+
+            // recharge energy
+            self.settle_recharge(&mut ship)?;
+            self.settle_shield(&mut ship);
+
+            // settle orders order
+            self.settle_top_order(&mut ship)?;
+
+            // save updated dynamics
+            self.ships.insert(ship_id, &ship);
+            if ship.position != position {
+                self.remove_tile_ship(position, ship_id);
+                self.add_tile_ship(ship.position, ship_id);
+            }
+            if ship.health == 0 {
+                self.destroy_ship(ship_id)?;
+            }
+            Ok(())
+        }
+
+        fn add_tile_ship(&mut self, position: (i32, i32), ship_id: ShipId) {
+            let mut ships = self.tile_ships.get(position).unwrap_or_default();
+            ships.push(ship_id);
+            self.tile_ships.insert(position, &ships);
+        }
+
+        fn remove_tile_ship(&mut self, position: (i32, i32), ship_id: ShipId) {
+            let mut ships = self.tile_ships.get(position).unwrap_or_default();
+            ships.retain(|id| *id != ship_id);
+            if ships.is_empty() {
+                self.tile_ships.remove(position);
+            } else {
+                self.tile_ships.insert(position, &ships);
+            }
+        }
+
+        // blocking_ship returns a ship other than ship_id on the tile, if collisions are enabled
+        fn blocking_ship(&self, position: (i32, i32), ship_id: ShipId) -> Option<ShipId> {
+            if !self.collisions_enabled {
+                return None;
+            }
+            self.tile_ships
+                .get(position)
+                .and_then(|ships| ships.into_iter().find(|id| *id != ship_id))
+        }
+
+        // stop_before_blocked shortens a path that ends on an occupied tile to the nearest free
+        // tile on it, the tiles the ship doesn't move are refunded. The path holds the tile after
+        // each step. Returns the tiles the ship moves.
+        fn stop_before_blocked(&self, ship: &mut Ship, path: &[(i32, i32)], cost: &mut u32) -> i32 {
+            let mut tiles = path.len();
+            if tiles == 0 {
+                return 0;
+            }
+            let tile_cost = *cost / tiles as u32;
+            let mut blocked = None;
+            while tiles > 0 {
+                match self.blocking_ship(path[tiles - 1], ship.id) {
+                    Some(blocked_by) => {
+                        blocked.get_or_insert(blocked_by);
+                        ship.energy += tile_cost;
+                        *cost -= tile_cost;
+                        tiles -= 1;
+                    }
+                    None => break,
+                }
+            }
+            if let Some(blocked_by) = blocked {
+                ship.move_progress_millis = 0;
+                self.env().emit_event(MovementBlocked {
+                    ship_id: ship.id,
+                    blocked_by,
+                });
+            }
+            tiles as i32
+        }
+
+        // set_collisions_enabled turns the blocking of occupied tiles on or off
+        #[ink(message)]
+        pub fn set_collisions_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            self.collisions_enabled = enabled;
+            Ok(())
+        }
+
+        // spawn_asteroid_field puts an asteroid with quantity units of the resource on every free
+        // tile within radius of the center, tiles with planets or asteroids are skipped
+        #[ink(message)]
+        pub fn spawn_asteroid_field(
+            &mut self,
+            center: (i32, i32),
+            radius: u32,
+            resource_type: ResourceType,
+            quantity: u32,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            if quantity == 0 {
+                return Err(Error::InvalidOrder);
+            }
+            for position in tiles_in_range(center, radius as i32, self.map_bounds) {
+                if self.tile_planet.contains(position) || self.asteroids.contains(position) {
+                    continue;
+                }
+                self.asteroids.insert(position, &(resource_type.clone(), quantity));
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_asteroid(&self, position: (i32, i32)) -> Option<Asteroid> {
+            self.asteroids.get(position)
+        }
+
+        // link_wormholes turns the two planets into the ends of a wormhole
+        #[ink(message)]
+        pub fn link_wormholes(&mut self, planet_id: PlanetId, other_id: PlanetId) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            if planet_id == other_id {
+                return Err(Error::InvalidOrder);
+            }
+            let mut planet = self.planets.get(planet_id).ok_or(Error::PlanetNotFound)?;
+            let mut other = self.planets.get(other_id).ok_or(Error::PlanetNotFound)?;
+            planet.set_wormhole_target(Some(other.get_position()));
+            other.set_wormhole_target(Some(planet.get_position()));
+            self.planets.insert(planet_id, &planet);
+            self.planets.insert(other_id, &other);
+            Ok(())
+        }
+
+        // admin_set_position moves a stuck or misbehaving ship, an active movement order is dropped
+        #[ink(message)]
+        pub fn admin_set_position(&mut self, ship_id: ShipId, position: (i32, i32)) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAuthorized);
+            }
+            if !in_bounds(position, self.map_bounds) {
+                return Err(Error::InvalidOrder);
+            }
+            let mut ship = self.load_ship(ship_id)?;
+            let block = self.env().block_number();
+            if let Some((order, start, _)) = ship.orders.first() {
+                if is_movement(order) && start.is_some_and(|start| start <= block) {
+                    ship.orders.remove(0);
+                    start_next_order(&mut ship, block);
+                }
+            }
+            ship.move_progress_millis = 0;
+            // a ship taken away from its planet isn't docked there anymore
+            if position != ship.position {
+                if let Some(planet_id) = ship.docked_at.take() {
+                    self.env().emit_event(ShipUndocked { ship_id, planet_id });
+                }
+            }
+            self.remove_tile_ship(ship.position, ship_id);
+            self.add_tile_ship(position, ship_id);
+            ship.position = position;
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(ShipMoved {
+                ship_id,
+                position,
+                energy_cost: 0,
+                wrapped: false,
+            });
+            Ok(())
+        }
+
+        // add_bounty adds the value transferred with the call to the ship's pending bounty
+        fn add_bounty(&mut self, ship_id: ShipId) {
+            let value = self.env().transferred_value();
+            if value > 0 {
+                let pending = self.pending_bounty.get(ship_id).unwrap_or_default();
+                self.pending_bounty.insert(ship_id, &(pending + value));
+            }
+        }
+
+        // pay_bounty moves the pending bounty of the ship to the keeper's claimable balance
+        fn pay_bounty(&mut self, ship_id: ShipId, keeper: AccountId) {
+            let amount = self.pending_bounty.get(ship_id).unwrap_or_default();
+            if amount == 0 {
+                return;
+            }
+            self.pending_bounty.remove(ship_id);
+            let earned = self.keeper_bounties.get(keeper).unwrap_or_default();
+            self.keeper_bounties.insert(keeper, &(earned + amount));
+            self.env().emit_event(BountyPaid {
+                ship_id,
+                keeper,
+                amount,
+            });
+        }
+
+        fn settle_recharge(
+            &self,
+            ship: &mut Ship,
+        ) -> Result<(), Error> {
+            let block = self.env().block_number();
+            let elapsed = block - ship.last_recharge;
+            #[cfg(feature = "debug-events")]
+            self.debug(&format!("recharge: block: {} last: {} elapsed: {}", block, ship.last_recharge, elapsed));
+            if elapsed > 0 && ship.energy < ship.max_energy {
+                ship.energy = self.recharged_energy(ship);
+                ship.last_recharge = block;
+                self.env().emit_event(ShipRecharged {
+                    ship_id: ship.id,
+                    new_energy: ship.energy,
+                });
+            }
+            Ok(())
+        }
+
+        // settle_shield regenerates the shield for the blocks since the last regeneration
+        fn settle_shield(&self, ship: &mut Ship) {
+            let block = self.env().block_number();
+            let elapsed = block - ship.last_shield_regen;
+            ship.last_shield_regen = block;
+            if elapsed == 0 || ship.shield >= ship.max_shield {
+                return;
+            }
+            ship.shield = ship
+                .shield
+                .saturating_add(elapsed.saturating_mul(ship.shield_regen_rate))
+                .min(ship.max_shield);
+            self.env().emit_event(ShieldRegenerated {
+                ship_id: ship.id,
+                new_shield: ship.shield,
+            });
+        }
+
+        // recharged_energy returns the energy the ship has once the blocks since
+        // its last recharge are accounted for
+        fn recharged_energy(&self, ship: &Ship) -> u32 {
+            let elapsed = self.env().block_number() - ship.last_recharge;
+            if ship.energy >= ship.max_energy {
+                return ship.energy;
+            }
+            let mut recharge_rate = ship.recharge_rate;
+            if self.is_at_owned_planet(ship) {
+                recharge_rate *= DOCKED_RECHARGE_MULTIPLIER;
+            }
+            ship.energy
+                .saturating_add(elapsed.saturating_mul(recharge_rate))
+                .min(ship.max_energy)
+        }
+
+        // is_at_owned_planet checks whether the ship is on a planet of its owner
+        fn is_at_owned_planet(&self, ship: &Ship) -> bool {
+            self.tile_planet
+                .get(ship.position)
+                .and_then(|planet_id| self.planets.get(planet_id))
+                .is_some_and(|planet| self.effective_owner(&planet) == Some(ship.owner))
+        }
+
+        // settle_top_order settles the front order. An order with a deadline is settled up to
+        // the deadline only and expires if it isn't completed by then, no matter how late the
+        // settlement happens.
+        fn settle_top_order(
+            &mut self,
+            ship: &mut Ship,
+        ) -> Result<(), Error> {
+            let (orders, deadline) = match ship.orders.first() {
+                Some((_, _, deadline)) => (ship.orders.len(), *deadline),
+                None => return Ok(()),
+            };
+            let now = self.env().block_number();
+            let block = deadline.map_or(now, |deadline| deadline.min(now));
+            self.settle_front_order(ship, block)?;
+
+            let deadline = match deadline {
+                Some(deadline) if now > deadline => deadline,
+                _ => return Ok(()),
+            };
+            // a completed order shortened the queue or handed its slot to another order
+            if ship.orders.len() != orders || ship.orders[0].2 != Some(deadline) {
+                return Ok(());
+            }
+            // the order wasn't completed in time, drop it
+            let order = ship.orders.remove(0).0;
+            start_next_order(ship, deadline);
+            self.env().emit_event(OrderExpired {
+                ship_id: ship.id,
+                order,
+            });
+            if ship.orders.is_empty() {
+                self.env().emit_event(ShipIdle { ship_id: ship.id });
+            }
+            Ok(())
+        }
+
+        // settle_front_order settles the front order up to block
+        fn settle_front_order(&mut self, ship: &mut Ship, block: Block) -> Result<(), Error> {
+            if let Some((_, Some(start), _)) = ship.orders.first() {
+                if *start > block {
+                    // scheduled for later
+                    return Ok(());
+                }
+            }
+            // a planet that is gone can't be worked on anymore, the order is dropped
+            // instead of blocking the settlement for good
+            if let Some(planet_id) = ship.orders.first().and_then(|(order, _, _)| order_planet(order)) {
+                if !self.planets.contains(planet_id) {
+                    let order = ship.orders.remove(0).0;
+                    start_next_order(ship, block);
+                    self.env().emit_event(OrderInvalidated {
+                        ship_id: ship.id,
+                        order,
+                        reason: Error::PlanetNotFound,
+                    });
+                    if ship.orders.is_empty() {
+                        self.env().emit_event(ShipIdle { ship_id: ship.id });
+                    }
+                    return Ok(());
+                }
+            }
+            match ship.orders.first().ok_or(Error::InvalidOrder)? {
+                (Order::Move((direction, speed, distance)), Some(start), _) => self.settle_movement(
+                    ship,
+                    direction.clone(),
+                    *speed,
+                    *distance,
+                    *start,
+                    block,
+                )?,
+                (Order::MoveBy((delta, speed)), Some(start), _) => {
+                    self.settle_move_by(ship, *delta, *speed, *start, block)?
+                }
+                (Order::Mine((planet_id, resource_type, duration)), Some(start), _) => self
+                    .settle_mining(
+                        ship,
+                        *planet_id,
+                        resource_type.clone(),
+                        *duration,
+                        *start,
+                        block,
+                        false,
+                    )?,
+                (Order::MineAndDeposit((planet_id, resource_type, duration)), Some(start), _) => self
+                    .settle_mining(
+                        ship,
+                        *planet_id,
+                        resource_type.clone(),
+                        *duration,
+                        *start,
+                        block,
+                        true,
+                    )?,
+                (Order::MineUntilFull((planet_id, resource_type)), Some(start), _) => self
+                    .settle_mine_until_full(ship, *planet_id, resource_type.clone(), *start, block)?,
+                (Order::MineAll((planet_id, duration)), Some(start), _) => {
+                    self.settle_mine_all(ship, *planet_id, *duration, *start, block)?
+                }
+                (Order::Repeat((inner, count)), Some(start), _) => {
+                    self.settle_repeat(ship, (**inner).clone(), *count, *start)
+                }
+                (Order::Bombard((planet_id, weapon_id)), Some(start), _) => {
+                    self.settle_bombard(ship, *planet_id, *weapon_id, *start, block)?
+                }
+                (Order::Idle(duration), Some(start), _) => {
+                    self.settle_idle(ship, *duration, *start, block)?
+                }
+                (Order::Salvage((position, duration)), Some(start), _) => {
+                    self.settle_salvage(ship, *position, *duration, *start, block)?
+                }
+                (Order::MineAsteroid((position, duration)), Some(start), _) => {
+                    self.settle_mine_asteroid(ship, *position, *duration, *start, block)?
+                }
+                (Order::Patrol((waypoint, other, speed)), Some(start), _) => {
+                    self.settle_patrol(ship, *waypoint, *other, *speed, *start, block)?
+                }
+                (Order::Follow((target_id, trailing_distance)), Some(start), _) => {
+                    self.settle_follow(ship, *target_id, *trailing_distance, *start, block)?
+                }
+                (Order::ReturnHome(speed), Some(start), _) => {
+                    self.settle_return_home(ship, *speed, *start, block)?
+                }
+                _ => return Err(Error::InvalidOrder),
+            };
+            if ship.orders.is_empty() {
+                // the last order was completed
+                self.env().emit_event(ShipIdle { ship_id: ship.id });
+            }
+            Ok(())
+        }
+
+        fn settle_movement(
+            &self,
+            ship: &mut Ship,
+            direction: Direction,
+            speed: i32,    // milli tiles per block
+            distance: i32, // tiles
+            start: Block,  // block number
+            block: Block,  // block number settled up to
+        ) -> Result<(), Error> {
+            let destination = move_in_direction(ship.position, &direction, distance, self.map_bounds);
+            let (tiles_to_move, mut cost, stalled) = match self.spend_movement(ship, speed, distance, destination, start, block) {
+                Some(movement) => movement,
+                None => return Ok(()),
+            };
+            let path: Vec<(i32, i32)> = (1..=tiles_to_move)
+                .map(|tiles| move_in_direction(ship.position, &direction, tiles, self.map_bounds))
+                .collect();
+            let tiles_to_move = self.stop_before_blocked(ship, &path, &mut cost);
+            let wrapped = crosses_edge(ship.position, &direction, tiles_to_move, self.map_bounds);
+            ship.position =
+                move_in_direction(ship.position, &direction, tiles_to_move, self.map_bounds);
+
+            let rest = distance - tiles_to_move;
+            if rest == 0 {
+                // order finished
+                ship.move_progress_millis = 0;
+                self.complete_top_order(ship, block);
+            } else {
+                let order = Order::Move((direction, speed, rest));
+                ship.orders[0].0 = order.clone();
+                ship.orders[0].1 = Some(block);
+                self.env().emit_event(OrderUpdated {
+                    ship_id: ship.id,
+                    order,
+                });
+            }
+
+            self.env().emit_event(ShipMoved {
+                ship_id: ship.id,
+                position: ship.position,
+                energy_cost: cost,
+                wrapped,
+            });
+            if stalled {
+                self.env().emit_event(ShipStalled {
+                    ship_id: ship.id,
+                    remaining_distance: rest,
+                });
+            }
+            if rest == 0 {
+                self.enter_wormhole(ship);
+            }
+
+            Ok(())
+        }
+
+        // spend_movement computes how many tiles (up to max_tiles) the ship can move since start
+        // and pays the energy for them. Returns the tiles, the energy cost and whether the ship ran
+        // out of energy on the way, or None if the ship hasn't made a full tile of progress yet.
+        fn spend_movement(
+            &self,
+            ship: &mut Ship,
+            speed: i32,              // milli tiles per block
+            max_tiles: i32,          // tiles
+            destination: (i32, i32), // where the move ends
+            start: Block,            // block number
+            block: Block,            // block number settled up to
+        ) -> Option<(i32, u32, bool)> {
+            let elapsed = (block - start) as i32;
+            // the cargo slows the ship down, the energy cost stays the same
+            let mut loaded_speed = loaded_speed(speed, ship.cargo.weight(), ship.cargo.max_weight());
+            // so does the gravity of nearby planets, only the start and destination tiles are
+            // checked to keep it cheap
+            if self.in_gravity_well(ship.position) || self.in_gravity_well(destination) {
+                loaded_speed = loaded_speed * GRAVITY_SPEED_PERCENT / 100;
+            }
+            // sub-tile progress of earlier settlements is carried over
+            let progress = ship.move_progress_millis + elapsed * loaded_speed;
+            let mut tiles_to_move = progress / 1000;
+            if tiles_to_move > max_tiles {
+                tiles_to_move = max_tiles;
+            }
+            if tiles_to_move <= 0 {
+                // the sub-tile progress is kept, so the milli-tile position advances every block
+                ship.move_progress_millis = progress;
+                ship.orders[0].1 = Some(block);
+                return None;
+            }
+
+            let cost = move_energy_per_tile(speed, ship.max_speed) as u32;
+            let stalled = (cost as i32) * tiles_to_move > ship.energy as i32;
+            if stalled {
+                // out of energy, the partial tile is lost
+                tiles_to_move = ship.energy as i32 / cost as i32;
+                ship.move_progress_millis = 0;
+            } else {
+                ship.move_progress_millis = progress - tiles_to_move * 1000;
+            }
+            ship.energy -= cost * tiles_to_move as u32;
+            self.env().emit_event(EnergyUsed {
+                ship_id: ship.id,
+                new_energy: ship.energy,
+            });
+            Some((tiles_to_move, cost * tiles_to_move as u32, stalled))
+        }
+
+        // settle_move_towards moves the ship on the shortest path towards target until it is
+        // within stop_distance tiles. The front order is kept, only its start is updated.
+        // Returns true once the ship is within stop_distance.
+        fn settle_move_towards(
+            &self,
+            ship: &mut Ship,
+            target: (i32, i32),
+            speed: i32,
+            stop_distance: i32,
+            start: Block,
+            block: Block,
+        ) -> Result<bool, Error> {
+            let remaining = hex_distance(ship.position, target) - stop_distance;
+            if remaining <= 0 {
+                return Ok(true);
+            }
+            let (tiles_to_move, mut cost, stalled) = match self.spend_movement(ship, speed, remaining, target, start, block) {
+                Some(movement) => movement,
+                None => return Ok(false),
+            };
+            let mut path = Vec::new();
+            let mut position = ship.position;
+            for _ in 0..tiles_to_move {
+                position = step_towards(position, target, self.map_bounds);
+                path.push(position);
+            }
+            let tiles_to_move = self.stop_before_blocked(ship, &path, &mut cost);
+            let mut wrapped = false;
+            for next in &path[..tiles_to_move as usize] {
+                wrapped |= step_wraps(ship.position, *next);
+                ship.position = *next;
+            }
+            ship.orders[0].1 = Some(block);
+            self.env().emit_event(ShipMoved {
+                ship_id: ship.id,
+                position: ship.position,
+                energy_cost: cost,
+                wrapped,
+            });
+            if stalled {
+                self.env().emit_event(ShipStalled {
+                    ship_id: ship.id,
+                    remaining_distance: remaining - tiles_to_move,
+                });
+            }
+            Ok(tiles_to_move == remaining)
+        }
+
+        // settle_move_by moves the ship tile by tile along the delta, the order keeps the part
+        // of the delta that is still left
+        fn settle_move_by(
+            &self,
+            ship: &mut Ship,
+            delta: (i32, i32, i32),
+            speed: i32,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            let remaining = cube_length(delta);
+            let (q, r, s) = offset_coordinates_to_cube_coordinates(ship.position);
+            let destination = wrap_position(
+                cube_coordinates_to_offset_coordinates((q + delta.0, r + delta.1, s + delta.2)),
+                self.map_bounds,
+            );
+            let (tiles_to_move, mut cost, stalled) = match self.spend_movement(ship, speed, remaining, destination, start, block) {
+                Some(movement) => movement,
+                None => return Ok(()),
+            };
+            let mut steps = Vec::new();
+            let mut rest = delta;
+            for _ in 0..tiles_to_move {
+                let direction = direction_along(rest);
+                let (uq, ur, us) = cube_direction(&direction);
+                rest = (rest.0 - uq, rest.1 - ur, rest.2 - us);
+                steps.push((direction, rest));
+            }
+            let mut position = ship.position;
+            let path: Vec<(i32, i32)> = steps
+                .iter()
+                .map(|(direction, _)| {
+                    position = move_in_direction(position, direction, 1, self.map_bounds);
+                    position
+                })
+                .collect();
+            let tiles_to_move = self.stop_before_blocked(ship, &path, &mut cost);
+            let mut delta = delta;
+            let mut wrapped = false;
+            for (direction, rest) in &steps[..tiles_to_move as usize] {
+                delta = *rest;
+                wrapped |= crosses_edge(ship.position, direction, 1, self.map_bounds);
+                ship.position = move_in_direction(ship.position, direction, 1, self.map_bounds);
+            }
+
+            if tiles_to_move == remaining {
+                ship.move_progress_millis = 0;
+                self.complete_top_order(ship, block);
+            } else {
+                let order = Order::MoveBy((delta, speed));
+                ship.orders[0].0 = order.clone();
+                ship.orders[0].1 = Some(block);
+                self.env().emit_event(OrderUpdated {
+                    ship_id: ship.id,
+                    order,
+                });
+            }
+            self.env().emit_event(ShipMoved {
+                ship_id: ship.id,
+                position: ship.position,
+                energy_cost: cost,
+                wrapped,
+            });
+            if stalled {
+                self.env().emit_event(ShipStalled {
+                    ship_id: ship.id,
+                    remaining_distance: remaining - tiles_to_move,
+                });
+            }
+            if tiles_to_move == remaining {
+                self.enter_wormhole(ship);
+            }
+            Ok(())
+        }
+
+        // enter_wormhole sends a ship that finished its move on a wormhole planet to the linked
+        // tile, ships only passing the tile aren't affected
+        fn enter_wormhole(&self, ship: &mut Ship) {
+            let target = self
+                .tile_planet
+                .get(ship.position)
+                .and_then(|planet_id| self.planets.get(planet_id))
+                .and_then(|planet| planet.get_wormhole_target());
+            if let Some(to) = target {
+                if let Some(blocked_by) = self.blocking_ship(to, ship.id) {
+                    // the exit is taken, the ship stays at the entrance
+                    self.env().emit_event(MovementBlocked {
+                        ship_id: ship.id,
+                        blocked_by,
+                    });
+                    return;
+                }
+                let from = ship.position;
+                ship.position = to;
+                self.env().emit_event(ShipTeleported {
+                    ship_id: ship.id,
+                    from,
+                    to,
+                });
+            }
+        }
+
+        fn settle_patrol(
+            &self,
+            ship: &mut Ship,
+            waypoint: (i32, i32),
+            other: (i32, i32),
+            speed: i32,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            if self.settle_move_towards(ship, waypoint, speed, 0, start, block)? {
+                // flip the waypoints, a patrol stays in the queue until it is dropped
+                ship.move_progress_millis = 0;
+                ship.orders[0].0 = Order::Patrol((other, waypoint, speed));
+                ship.orders[0].1 = Some(block);
+                self.env().emit_event(WaypointReached {
+                    ship_id: ship.id,
+                    position: waypoint,
+                });
+            }
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn settle_mining(
+            &mut self,
+            ship: &mut Ship,
+            planet_id: PlanetId,
+            resource_type: ResourceType,
+            duration: Block,
+            start: Block,
+            block: Block,
+            deposit: bool, // put the yield into the planet's inventory if the ship's owner owns it
+        ) -> Result<(), Error> {
+            let elapsed = block - start;
+            if elapsed < duration {
+                // not enough time has passed
+                return Ok(());
+            }
+            if self.extract_resources(ship, planet_id, resource_type, duration, deposit)? {
+                // order finished, remove it
+                self.complete_top_order(ship, block);
+            }
+            Ok(())
+        }
+
+        // extract_resources mines the planet for the given number of blocks,
+        // returns false if the ship doesn't have the energy for it
+        fn extract_resources(
+            &mut self,
+            ship: &mut Ship,
+            planet_id: PlanetId,
+            resource_type: ResourceType,
+            blocks: Block,
+            deposit: bool,
+        ) -> Result<bool, Error> {
+            let cost = self.mine_energy_cost * blocks;
+            if cost > ship.energy {
+                // not enough energy
+                return Ok(false);
+            }
+            let mut planet = self.mining_planet(ship, planet_id, Some(&resource_type))?;
+
+            // extract the resource and put it into the ship's cargo,
+            // energy is only consumed if the whole amount fits
+            let amount = planet.get_mining_rate(&resource_type) * blocks;
+            let fee = mining_fee(ship, &planet, &resource_type, amount);
+            let amount = amount - fee;
+            let item = Item::Resource(Resource::new(resource_type.clone(), amount));
+            if !deposit && !ship.cargo.has_space(&item) {
+                return Err(Error::NotEnoughCargoSpace);
+            }
+            // the cargo is the fallback if the planet's inventory is full
+            let deposited = deposit
+                && planet.get_owner() == Some(ship.owner)
+                && planet.add_item(item.clone()).is_ok();
+            if deposited {
+                self.planets.insert(planet_id, &planet);
+                self.env().emit_event(ResourcesDeposited {
+                    planet_id,
+                    ship_id: ship.id,
+                    resource_type: resource_type.clone(),
+                    amount,
+                });
+            } else {
+                ship.cargo
+                    .add_item(item)
+                    .map_err(|_| Error::NotEnoughCargoSpace)?;
+            }
+            self.pay_mining_fee(&mut planet, &resource_type, fee);
+            ship.energy -= cost;
+            self.env().emit_event(EnergyUsed {
+                ship_id: ship.id,
+                new_energy: ship.energy,
+            });
+            self.env().emit_event(ResourceMined {
+                ship_id: ship.id,
+                planet_id: planet_id,
+                resource_type: resource_type.clone(),
+                quantity: amount,
+            });
+            self.add_mined_total(ship.id, resource_type, amount);
+            self.award_xp(ship, amount);
+            Ok(true)
+        }
+
+        // settle_mine_asteroid mines the asteroid once the duration has passed,
+        // the asteroid is gone once its last unit is mined
+        fn settle_mine_asteroid(
+            &mut self,
+            ship: &mut Ship,
+            position: (i32, i32),
+            duration: Block,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            if block - start < duration {
+                // not enough time has passed
+                return Ok(());
+            }
+            let cost = self.mine_energy_cost * duration;
+            if cost > ship.energy {
+                // not enough energy
+                return Ok(());
+            }
+            let (resource_type, left) = match self.asteroids.get(position) {
+                Some(asteroid) => asteroid,
+                None => {
+                    // someone else was faster
+                    self.complete_top_order(ship, block);
+                    return Ok(());
+                }
+            };
+            if hex_distance(position, ship.position) > MINING_RANGE {
+                // the ship drifted away from the asteroid, the order is dropped instead of
+                // blocking the settlement for good
+                let order = ship.orders.remove(0).0;
+                start_next_order(ship, block);
+                self.env().emit_event(OrderInvalidated {
+                    ship_id: ship.id,
+                    order,
+                    reason: Error::TargetOutOfRange,
+                });
+                if ship.orders.is_empty() {
+                    self.env().emit_event(ShipIdle { ship_id: ship.id });
+                }
+                return Ok(());
+            }
+
+            let amount = (ASTEROID_MINING_RATE * duration).min(left);
+            ship.cargo
+                .add_item(Item::Resource(Resource::new(resource_type.clone(), amount)))
+                .map_err(|_| Error::NotEnoughCargoSpace)?;
+            if amount == left {
+                self.asteroids.remove(position);
+                self.env().emit_event(AsteroidDepleted { position });
+            } else {
+                self.asteroids.insert(position, &(resource_type.clone(), left - amount));
+            }
+            ship.energy -= cost;
+            self.env().emit_event(EnergyUsed {
+                ship_id: ship.id,
+                new_energy: ship.energy,
+            });
+            self.env().emit_event(AsteroidMined {
+                ship_id: ship.id,
+                position,
+                resource_type: resource_type.clone(),
+                quantity: amount,
+            });
+            self.add_mined_total(ship.id, resource_type, amount);
+            self.award_xp(ship, amount);
+            self.complete_top_order(ship, block);
+            Ok(())
+        }
+
+        // settle_mine_until_full mines the elapsed blocks, limited by energy and cargo space.
+        // The order is completed once the cargo can't take more of the resource or the
+        // ship runs out of energy.
+        fn settle_mine_until_full(
+            &mut self,
+            ship: &mut Ship,
+            planet_id: PlanetId,
+            resource_type: ResourceType,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            let elapsed = block - start;
+            if elapsed == 0 {
+                return Ok(());
+            }
+            let mut planet = self.mining_planet(ship, planet_id, Some(&resource_type))?;
+            let rate = planet.get_mining_rate(&resource_type);
+            let space = ship.cargo.resource_space(&resource_type);
+            let energy_per_block = self.mine_energy_cost;
+            if space == 0 || rate == 0 || ship.energy < energy_per_block {
+                self.complete_top_order(ship, block);
+                return Ok(());
+            }
+
+            // only pay for the blocks needed to fill up the cargo
+            let blocks = elapsed
+                .min(ship.energy / energy_per_block)
+                .min(space.div_ceil(rate));
+            let amount = (rate * blocks).min(space);
+            let fee = mining_fee(ship, &planet, &resource_type, amount);
+            let amount = amount - fee;
+            ship.cargo
+                .add_item(Item::Resource(Resource::new(resource_type.clone(), amount)))
+                .map_err(|_| Error::NotEnoughCargoSpace)?;
+            self.pay_mining_fee(&mut planet, &resource_type, fee);
+            ship.energy -= energy_per_block * blocks;
+            self.env().emit_event(EnergyUsed {
+                ship_id: ship.id,
+                new_energy: ship.energy,
+            });
+            self.env().emit_event(ResourceMined {
+                ship_id: ship.id,
+                planet_id,
+                resource_type: resource_type.clone(),
+                quantity: amount,
+            });
+            self.add_mined_total(ship.id, resource_type.clone(), amount);
+            self.award_xp(ship, amount);
+
+            if ship.cargo.resource_space(&resource_type) == 0 {
+                self.complete_top_order(ship, block);
+            } else {
+                ship.orders[0].1 = Some(block);
+            }
+            Ok(())
+        }
+
+        // settle_mine_all mines every resource of the planet once the duration has passed.
+        // Resources that don't fit into the cargo are left out and aren't charged for.
+        fn settle_mine_all(
+            &mut self,
+            ship: &mut Ship,
+            planet_id: PlanetId,
+            duration: Block,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            let elapsed = block - start;
+            if elapsed < duration {
+                // not enough time has passed
+                return Ok(());
+            }
+            let mut planet = self.mining_planet(ship, planet_id, None)?;
+            let resources = planet.get_resources();
+            let cost_per_resource = self.mine_energy_cost * duration;
+            if cost_per_resource * resources.len() as u32 > ship.energy {
+                // not enough energy
+                return Ok(());
+            }
+
+            let mut mined = 0;
+            for resource_type in resources {
+                let amount = (planet.get_mining_rate(&resource_type) * duration)
+                    .min(ship.cargo.resource_space(&resource_type));
+                if amount == 0 {
+                    continue;
+                }
+                let fee = mining_fee(ship, &planet, &resource_type, amount);
+                let amount = amount - fee;
+                ship.cargo
+                    .add_item(Item::Resource(Resource::new(resource_type.clone(), amount)))
+                    .map_err(|_| Error::NotEnoughCargoSpace)?;
+                self.pay_mining_fee(&mut planet, &resource_type, fee);
+                self.env().emit_event(ResourceMined {
+                    ship_id: ship.id,
+                    planet_id,
+                    resource_type: resource_type.clone(),
+                    quantity: amount,
+                });
+                self.add_mined_total(ship.id, resource_type, amount);
+                self.award_xp(ship, amount);
+                mined += 1;
+            }
+            if mined == 0 {
+                return Err(Error::NotEnoughCargoSpace);
+            }
+            ship.energy -= cost_per_resource * mined;
+            self.env().emit_event(EnergyUsed {
+                ship_id: ship.id,
+                new_energy: ship.energy,
+            });
+
+            // order finished, remove it
+            self.complete_top_order(ship, block);
+            Ok(())
+        }
+
+        // pay_mining_fee puts the fee into the planet's inventory for its owner
+        fn pay_mining_fee(&mut self, planet: &mut Planet, resource_type: &ResourceType, fee: u32) {
+            let owner = match planet.get_owner() {
+                Some(owner) if fee > 0 => owner,
+                _ => return,
+            };
+            if planet
+                .add_item(Item::Resource(Resource::new(resource_type.clone(), fee)))
+                .is_err()
+            {
+                return;
+            }
+            self.planets.insert(planet.get_id(), planet);
+            self.env().emit_event(MiningFeePaid {
+                planet_id: planet.get_id(),
+                owner,
+                resource_type: resource_type.clone(),
+                amount: fee,
+            });
+        }
+
+        // award_xp adds experience to the ship, every level reached raises
+        // its max health and max energy
+        fn award_xp(&self, ship: &mut Ship, xp: u32) {
+            ship.xp = ship.xp.saturating_add(xp);
+            while ship.xp >= xp_for_level(ship.level + 1) {
+                ship.level += 1;
+                ship.max_health += LEVEL_HEALTH_BONUS;
+                ship.max_energy += LEVEL_ENERGY_BONUS;
+                self.env().emit_event(ShipLeveledUp {
+                    ship_id: ship.id,
+                    new_level: ship.level,
+                });
+            }
+        }
+
+        // add_mined_total adds the amount to the ship's and the global running total of the resource
+        fn add_mined_total(&mut self, ship_id: ShipId, resource_type: ResourceType, amount: u32) {
+            let global = self.total_mined.get(&resource_type).unwrap_or_default();
+            self.total_mined
+                .insert(&resource_type, &global.saturating_add(amount as u64));
+            let mut totals = self.mined_totals.get(ship_id).unwrap_or_default();
+            match totals.iter_mut().find(|(rt, _)| *rt == resource_type) {
+                Some((_, total)) => *total += amount as u64,
+                None => totals.push((resource_type, amount as u64)),
+            }
+            self.mined_totals.insert(ship_id, &totals);
+        }
+
+        // in_gravity_well tells whether a planet is within GRAVITY_RADIUS of the position
+        fn in_gravity_well(&self, position: (i32, i32)) -> bool {
+            tiles_in_range(position, GRAVITY_RADIUS, self.map_bounds)
+                .into_iter()
+                .any(|tile| self.tile_planet.contains(tile))
+        }
+
+        // ships_in_range returns the ships within radius tiles of the position
+        fn ships_in_range(&self, position: (i32, i32), radius: i32) -> Vec<ShipId> {
+            tiles_in_range(position, radius, self.map_bounds)
+                .into_iter()
+                .flat_map(|tile| self.tile_ships.get(tile).unwrap_or_default())
+                .collect()
+        }
+
+        // mining_planet checks that the ship can mine the resource at the planet,
+        // without a resource type any of the planet's resources
+        fn mining_planet(
+            &mut self,
+            ship: &Ship,
+            planet_id: PlanetId,
+            resource_type: Option<&ResourceType>,
+        ) -> Result<Planet, Error> {
+            let planet = self.load_planet(planet_id)?;
+            if hex_distance(planet.get_position(), ship.position) > MINING_RANGE {
+                // mining only settles when the ship is in range of the planet
+                return Err(Error::ShipNotAtPlanet);
+            }
+            if resource_type.is_some_and(|rt| !planet.get_resources().contains(rt)) {
+                // planet does not have the resource
+                return Err(Error::ResourceNotFound);
+            }
+            Ok(planet)
+        }
+
+        // settle_follow moves the ship towards the target ship at max speed. The order is
+        // completed once the target ship doesn't exist anymore.
+        fn settle_follow(
+            &self,
+            ship: &mut Ship,
+            target_id: ShipId,
+            trailing_distance: u32,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            let target = match self.ships.get(target_id) {
+                Some(target) => target,
+                None => {
+                    ship.move_progress_millis = 0;
+                    self.complete_top_order(ship, block);
+                    return Ok(());
+                }
+            };
+            let speed = ship.max_speed;
+            if self.settle_move_towards(ship, target.position, speed, trailing_distance as i32, start, block)? {
+                // close enough, wait without accumulating progress
+                ship.move_progress_millis = 0;
+                ship.orders[0].1 = Some(block);
+            }
+            Ok(())
+        }
+
+        // settle_return_home moves the ship to its home planet, the order is completed on arrival
+        // or if there is no home planet anymore
+        fn settle_return_home(&self, ship: &mut Ship, speed: i32, start: Block, block: Block) -> Result<(), Error> {
+            let home = match ship.home_planet.and_then(|planet_id| self.planets.get(planet_id)) {
+                Some(home) => home,
+                None => {
+                    ship.move_progress_millis = 0;
+                    self.complete_top_order(ship, block);
+                    return Ok(());
+                }
+            };
+            if self.settle_move_towards(ship, home.get_position(), speed, 0, start, block)? {
+                ship.move_progress_millis = 0;
+                self.complete_top_order(ship, block);
+            }
+            Ok(())
+        }
+
+        // settle_bombard fires the weapon at the planet once per elapsed block, as far as the
+        // energy allows. The order is completed once the planet is lost or isn't hostile anymore.
+        fn settle_bombard(
+            &mut self,
+            ship: &mut Ship,
+            planet_id: PlanetId,
+            weapon_id: ItemId,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            let elapsed = block - start;
+            if elapsed == 0 {
+                return Ok(());
+            }
+            let mut planet = self.load_planet(planet_id)?;
+            let owner = match planet.get_owner() {
+                Some(owner) if owner != ship.owner => owner,
+                _ => {
+                    // nothing left to contest
+                    self.complete_top_order(ship, block);
+                    return Ok(());
+                }
+            };
+            if hex_distance(ship.position, planet.get_position()) > 1 {
+                return Err(Error::ShipNotAtPlanet);
+            }
+            let weapon = equipped_weapon(ship, weapon_id)?;
+
+            let shots = match weapon.energy_cost() {
+                0 => elapsed,
+                cost => elapsed.min(ship.energy / cost),
+            };
+            ship.orders[0].1 = Some(block);
+            if shots == 0 {
+                // not enough energy
+                return Ok(());
+            }
+            ship.energy -= shots * weapon.energy_cost();
+            self.env().emit_event(EnergyUsed {
+                ship_id: ship.id,
+                new_energy: ship.energy,
+            });
+
+            let damage = shots * weapon.damage();
+            let lost = planet.take_damage(damage);
+            self.env().emit_event(PlanetBombarded {
+                planet_id,
+                ship_id: ship.id,
+                damage,
+                health: planet.get_health(),
+            });
+            // the planet fires back at every shot, a ship without health left is destroyed
+            // once it is settled
+            let return_fire = apply_damage(ship, shots * planet.get_defense());
+            if return_fire > 0 {
+                self.env().emit_event(PlanetDefended {
+                    planet_id,
+                    ship_id: ship.id,
+                    damage: return_fire,
+                });
+            }
+            if lost {
+                self.env().emit_event(PlanetLost {
+                    planet_id,
+                    previous_owner: owner,
+                });
+                self.complete_top_order(ship, block);
+            }
+            // the queue is cleared after the order was completed, a ship can take the planet
+            // with its last shot
+            if ship.health == 0 {
+                ship.orders.clear();
+            }
+            self.planets.insert(planet_id, &planet);
+            Ok(())
+        }
+
+        // settle_repeat hands out the next run of the inner order in front of the repeat.
+        // When the last run is handed out, the repeat is completed.
+        fn settle_repeat(&self, ship: &mut Ship, inner: Order, count: u32, start: Block) {
+            if count == 1 {
+                let order = ship.orders.remove(0).0;
+                ship.orders.insert(0, (inner, Some(start), None));
+                self.env().emit_event(OrderCompleted {
+                    ship_id: ship.id,
+                    order,
+                });
+                return;
+            }
+            // the repeat is re-armed once the run is completed
+            let remaining = count.saturating_sub(1);
+            ship.orders[0].0 = Order::Repeat((Box::new(inner.clone()), remaining));
+            ship.orders[0].1 = None;
+            ship.orders.insert(0, (inner, Some(start), None));
+        }
+
+        fn settle_idle(
+            &self,
+            ship: &mut Ship,
+            duration: Duration,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            let end = start + duration;
+            if block < end {
+                return Ok(());
+            }
+            // the next order starts right when the idle time is over
+            self.complete_top_order(ship, end);
+            Ok(())
+        }
+
+        // complete_top_order removes the front order and starts the next one at `block`,
+        // or later if the next one is scheduled
+        fn complete_top_order(&self, ship: &mut Ship, block: Block) {
+            let order = ship.orders.remove(0).0;
+            start_next_order(ship, block);
+            self.env().emit_event(OrderCompleted {
+                ship_id: ship.id,
+                order,
+            });
+        }
+
+        // upgrade_ship raises a stat by its step up to its cap, consuming the upgrade cost from cargo
+        fn upgrade_ship(&mut self, ship_id: ShipId, stat: ShipStat) -> Result<(), Error> {
+            let mut ship = self.load_ship(ship_id)?;
+            if ship.owner != self.env().caller() {
+                return Err(Error::NotShipOwner);
+            }
+            let (current, step, cap) = match stat {
+                ShipStat::Speed => (ship.max_speed as u32, SPEED_UPGRADE_STEP, MAX_SPEED_CAP),
+                ShipStat::EnergyCapacity => (ship.max_energy, ENERGY_UPGRADE_STEP, MAX_ENERGY_CAP),
+                ShipStat::RechargeRate => (
+                    ship.recharge_rate,
+                    RECHARGE_UPGRADE_STEP,
+                    MAX_RECHARGE_RATE_CAP,
+                ),
+                ShipStat::CargoSize => (ship.max_cargo_size, CARGO_UPGRADE_STEP, MAX_CARGO_SIZE_CAP),
+                ShipStat::InventorySize => (
+                    ship.max_inventory_size,
+                    INVENTORY_UPGRADE_STEP,
+                    MAX_INVENTORY_SIZE_CAP,
+                ),
+            };
+            if current >= cap {
+                return Err(Error::UpgradeLimitReached);
+            }
+            let (resource_type, amount) = upgrade_cost(&stat);
+            ship.cargo.consume_resource(&resource_type, amount)?;
+            let new_value = (current + step).min(cap);
+            match stat {
+                ShipStat::Speed => ship.max_speed = new_value as i32,
+                ShipStat::EnergyCapacity => ship.max_energy = new_value,
+                ShipStat::RechargeRate => ship.recharge_rate = new_value,
+                ShipStat::CargoSize => {
+                    ship.max_cargo_size = new_value;
+                    ship.cargo.set_max_size(new_value);
+                }
+                ShipStat::InventorySize => {
+                    ship.max_inventory_size = new_value;
+                    ship.inventory.set_max_size(new_value);
+                }
+            }
+            self.ships.insert(ship_id, &ship);
+            self.env().emit_event(ShipUpgraded {
+                ship_id,
+                stat,
+                new_value,
+            });
+            Ok(())
+        }
+
+        // settle_salvage moves the items of the wreck into the cargo once the duration has
+        // passed. Items that don't fit stay in the wreck, an empty wreck is removed.
+        fn settle_salvage(
+            &mut self,
+            ship: &mut Ship,
+            position: (i32, i32),
+            duration: Block,
+            start: Block,
+            block: Block,
+        ) -> Result<(), Error> {
+            if block - start < duration {
+                // not enough time has passed
+                return Ok(());
+            }
+            if ship.position != position {
+                return Err(Error::NotAtWreck);
+            }
+            let mut wreck = match self.wrecks.get(position) {
+                Some(wreck) => wreck,
+                None => {
+                    // someone else was faster
+                    self.complete_top_order(ship, block);
+                    return Ok(());
+                }
+            };
+
+            let mut salvaged = 0;
+            for item in wreck.items() {
+                if ship.cargo.is_full() {
+                    break;
+                }
+                if ship.cargo.add_item(item.clone()).is_ok() {
+                    wreck.remove_item(item.id());
+                    salvaged += 1;
+                }
+            }
+            if wreck.items().is_empty() {
+                self.wrecks.remove(position);
+            } else {
+                self.wrecks.insert(position, &wreck);
+            }
+            self.env().emit_event(WreckSalvaged {
+                ship_id: ship.id,
+                position,
+                items: salvaged,
+            });
+
+            // order finished, remove it
+            self.complete_top_order(ship, block);
+            Ok(())
+        }
+
+        // destroy_ship removes the ship, its cargo is left behind as a wreck at its position
+        fn destroy_ship(&mut self, ship_id: ShipId) -> Result<(), Error> {
+            let ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+            let mut cargo = ship.cargo.items();
+            // open trades are cancelled, their locked offers end up in the wreck
+            for trade_id in self.ship_trades.get(ship_id).unwrap_or_default() {
+                if let Some(trade) = self.trades.get(trade_id) {
+                    let (offer_type, offer_quantity) = trade.offer;
+                    cargo.push(Item::Resource(Resource::new(offer_type, offer_quantity)));
+                    self.trades.remove(trade_id);
+                }
+            }
+            self.ship_trades.remove(ship_id);
+            if !cargo.is_empty() {
+                let mut wreck = self
+                    .wrecks
+                    .get(ship.position)
+                    .unwrap_or_else(|| Inventory::new(0));
+                // a slot for every stack the items need, offers can be larger than a stack
+                let slots: usize = cargo
+                    .iter()
+                    .map(|item| match item {
+                        Item::Resource(resource) => {
+                            resource.quantity().div_ceil(max_stack_size(&resource.resource_type())) as usize
+                        }
+                        _ => 1,
+                    })
+                    .sum();
+                wreck.set_max_size((wreck.items().len() + slots) as u32);
+                for item in cargo {
+                    // the wreck never keeps the ship from being destroyed
+                    let _ = wreck.add_item(item);
+                }
+                self.wrecks.insert(ship.position, &wreck);
+            }
+            self.ships.remove(ship_id);
+            self.remove_ship_id(ship_id);
+            self.ship_count -= 1;
+            self.remove_tile_ship(ship.position, ship_id);
+            if !ship.name.is_empty() {
+                self.name_to_ship.remove(&ship.name);
+            }
+            let mut owner_ships = self.owner_ships.get(ship.owner).unwrap_or_default();
+            owner_ships.retain(|id| *id != ship_id);
+            self.owner_ships.insert(ship.owner, &owner_ships);
+            // the unpaid bounty goes back to the owner, it can be claimed like a keeper's
+            let bounty = self.pending_bounty.get(ship_id).unwrap_or_default();
+            if bounty > 0 {
+                let earned = self.keeper_bounties.get(ship.owner).unwrap_or_default();
+                self.keeper_bounties.insert(ship.owner, &(earned + bounty));
+                self.pending_bounty.remove(ship_id);
+            }
+            self.ship_listings.remove(ship_id);
+            self.env().emit_event(ShipDestroyed {
+                ship_id,
+                position: ship.position,
+            });
+            Ok(())
+        }
+
+        // load_ship gets a ship that is about to be changed, with its vitals normalized
+        fn load_ship(&self, ship_id: ShipId) -> Result<Ship, Error> {
+            let mut ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+            clamp_vitals(&mut ship);
+            Ok(ship)
+        }
+
+        fn remove_ship_id(&mut self, ship_id: ShipId) {
+            let mut ships = self.ship_ids.get_or_default();
+            if let Some(index) = ships.iter().position(|id| *id == ship_id) {
+                ships.remove(index);
+                self.ship_ids.set(&ships);
+            }
+        }
+
+        #[cfg(feature = "debug-events")]
+        fn debug(&self, msg: &str) {
+            self.env().emit_event(DebugEvent {
+                value: msg.to_string(),
+            });
+        }
+    }
+
+    // apply_damage lets the shield take the damage first, the rest goes to the health.
+    // Returns the damage that was actually dealt.
+    fn apply_damage(ship: &mut Ship, damage: u32) -> u32 {
+        let absorbed = damage.min(ship.shield);
+        ship.shield -= absorbed;
+        let taken = (damage - absorbed).min(ship.health);
+        ship.health -= taken;
+        absorbed + taken
+    }
+
+    // clamp_vitals makes sure energy, health and shield never exceed their maximum,
+    // so later subtractions can rely on the invariant
+    fn clamp_vitals(ship: &mut Ship) {
+        ship.energy = ship.energy.min(ship.max_energy());
+        ship.health = ship.health.min(ship.max_health());
+        ship.shield = ship.shield.min(ship.max_shield);
+    }
+
+    // mining_fee is the part of the mined amount that goes to the owner of the planet.
+    // Owners mine for free, and there's no fee if the planet's inventory can't take it.
+    fn mining_fee(ship: &Ship, planet: &Planet, resource_type: &ResourceType, amount: u32) -> u32 {
+        match planet.get_owner() {
+            Some(owner) if owner != ship.owner => {}
+            _ => return 0,
+        }
+        // rounded up, splitting the mining into small orders doesn't avoid the fee
+        let fee = (amount * MINING_FEE_PERCENT).div_ceil(100);
+        if planet.get_inventory().resource_space(resource_type) < fee {
+            return 0;
+        }
+        fee
+    }
+
+    // is_movement tells whether the order moves the ship
+    fn is_movement(order: &Order) -> bool {
+        match order {
+            Order::Move(_)
+            | Order::MoveBy(_)
+            | Order::Patrol(_)
+            | Order::Follow(_)
+            | Order::ReturnHome(_) => true,
+            Order::Repeat((inner, _)) => is_movement(inner),
+            _ => false,
+        }
+    }
+
+    // order_planet returns the planet the order works on
+    fn order_planet(order: &Order) -> Option<PlanetId> {
+        match order {
+            Order::Mine((planet_id, _, _))
+            | Order::MineAndDeposit((planet_id, _, _))
+            | Order::MineUntilFull((planet_id, _))
+            | Order::MineAll((planet_id, _))
+            | Order::Bombard((planet_id, _)) => Some(*planet_id),
+            _ => None,
+        }
+    }
+
+    // armor_defense sums up the defense of the equipped armor
+    fn armor_defense(ship: &Ship) -> u32 {
+        ship.equipped
+            .iter()
+            .filter_map(|id| match ship.inventory.get_item(*id) {
+                Some(Item::Armor(armor)) => Some(armor.defense()),
+                _ => None,
+            })
+            .sum()
+    }
+
+    // equipped_weapon returns the weapon if it is in the inventory and equipped
+    fn equipped_weapon(ship: &Ship, item_id: ItemId) -> Result<Weapon, Error> {
+        if !ship.equipped.contains(&item_id) {
+            return Err(Error::ItemNotFound);
+        }
+        match ship.inventory.get_item(item_id) {
+            Some(Item::Weapon(weapon)) => Ok(weapon.clone()),
+            Some(_) => Err(Error::InvalidOrder),
+            None => Err(Error::ItemNotFound),
+        }
+    }
+
+    // start_next_order starts the front order at block, or later if it is scheduled
+    fn start_next_order(ship: &mut Ship, block: Block) {
+        if let Some(next) = ship.orders.first_mut() {
+            next.1 = Some(next.1.map_or(block, |start_at| start_at.max(block)));
+        }
+    }
+
+    fn upgrade_cost(stat: &ShipStat) -> (ResourceType, u32) {
+        match stat {
+            ShipStat::Speed => (ResourceType::Iron, 32),
+            ShipStat::EnergyCapacity => (ResourceType::Copper, 16),
+            ShipStat::RechargeRate => (ResourceType::Copper, 32),
+            ShipStat::CargoSize => (ResourceType::Iron, 48),
+            ShipStat::InventorySize => (ResourceType::Copper, 24),
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use crate::inventory::Armor;
+        use crate::mechanics::{
+            cube_coordinates_to_offset_coordinates, offset_coordinates_to_cube_coordinates,
+        };
+
+        type Event = <Rareships as ::ink::reflect::ContractEventBase>::Type;
+
+        #[ink::test]
+        fn hex_neighbors_works() {
+            let contract = Rareships::new();
+            assert_eq!(
+                contract.get_neighbors((0, 0)),
+                vec![(9999, 9999), (0, 9999), (1, 0), (0, 1), (9999, 1), (9999, 0)]
+            );
+
+            for position in [(5, 5), (4, 6)] {
+                for neighbor in hex_neighbors(position, (100, 100)) {
+                    assert_eq!(hex_distance(position, neighbor), 1);
+                    let cube = offset_coordinates_to_cube_coordinates(neighbor);
+                    assert_eq!(cube.0 + cube.1 + cube.2, 0);
+                    assert_eq!(cube_coordinates_to_offset_coordinates(cube), neighbor);
+                }
+            }
+        }
+
+        #[ink::test]
+        fn blast_hits_clustered_ships() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            for ship_id in 2..=4 {
+                assert_eq!(contract.spawn(ship_id), Ok(()));
+            }
+            place_ship(&mut contract, 2, (2, 0));
+            place_ship(&mut contract, 3, (3, 0));
+            place_ship(&mut contract, 4, (5, 0));
+            let mut ship = contract.get_ship(1).unwrap();
+            let weapon = Weapon::new(30, 3, 10).with_blast_radius(1);
+            assert!(ship.inventory.add_item(Item::Weapon(weapon)).is_ok());
+            contract.ships.insert(1, &ship);
+            let mut ship = contract.get_ship(3).unwrap();
+            assert!(ship.inventory.add_item(Item::Armor(Armor::new(10))).is_ok());
+            contract.ships.insert(3, &ship);
+            assert_eq!(contract.equip_item(3, 1), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+
+            assert_eq!(contract.fire_weapon(1, 1, (2, 0)), Err(Error::ItemNotFound));
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+            assert_eq!(contract.fire_weapon(1, 1, (5, 0)), Err(Error::TargetOutOfRange));
+            assert_eq!(contract.fire_weapon(1, 1, (2, 0)), Ok(()));
+
+            let health = |contract: &Rareships, ship_id| contract.get_ship(ship_id).unwrap().health;
+            assert_eq!(health(&contract, 2), 70);
+            assert_eq!(health(&contract, 3), 80);
+            assert_eq!(health(&contract, 4), 100);
+            assert_eq!(contract.get_ship_energy(1), Some(990));
+
+            // the unarmored ship is destroyed after a few more hits
+            for _ in 0..3 {
+                assert_eq!(contract.fire_weapon(1, 1, (2, 0)), Ok(()));
+            }
+            assert!(contract.get_ship(2).is_none());
+            assert_eq!(health(&contract, 3), 20);
+        }
+
+        #[ink::test]
+        fn blasts_wrap_around_the_map_edges() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.spawn(2), Ok(()));
+            let edge = (contract.map_bounds.0 - 1, 0);
+            place_ship(&mut contract, 2, edge);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            place_ship(&mut contract, 1, (1, 0));
+            let mut ship = contract.get_ship(1).unwrap();
+            let weapon = Weapon::new(30, 1, 10).with_blast_radius(1);
+            assert!(ship.inventory.add_item(Item::Weapon(weapon)).is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+
+            assert_eq!(contract.targets_in_range(1, 1), Vec::<ShipId>::new());
+            assert_eq!(contract.fire_weapon(1, 1, (0, 0)), Ok(()));
+            assert_eq!(contract.get_ship(2).unwrap().health, 70);
+
+            // the ship across the edge is in range as well
+            place_ship(&mut contract, 1, (0, 0));
+            assert_eq!(contract.targets_in_range(1, 1), vec![2]);
+            assert_eq!(contract.fire_weapon(1, 1, edge), Ok(()));
+        }
+
+        #[ink::test]
+        fn targets_in_weapon_range() {
+            let mut contract = Rareships::new();
+            for ship_id in 1..=4 {
+                assert_eq!(contract.spawn(ship_id), Ok(()));
+            }
+            place_ship(&mut contract, 1, (5, 5));
+            place_ship(&mut contract, 2, (7, 5));
+            place_ship(&mut contract, 3, (8, 5));
+            place_ship(&mut contract, 4, (5, 5));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(10, 2, 1))).is_ok());
+            contract.ships.insert(1, &ship);
+
+            // the weapon has to be equipped
+            assert_eq!(contract.targets_in_range(1, 1), Vec::<ShipId>::new());
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+            // ship 2 is just inside the range of 2, ship 3 just outside
+            assert_eq!(contract.targets_in_range(1, 1), vec![2, 4]);
+            assert_eq!(contract.targets_in_range(1, 2), Vec::<ShipId>::new());
+            assert_eq!(contract.targets_in_range(9, 1), Vec::<ShipId>::new());
+        }
+
+        #[ink::test]
+        fn shields_absorb_damage_first() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.spawn(2), Ok(()));
+            place_ship(&mut contract, 2, (1, 0));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(30, 1, 10))).is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+
+            // shields charge up over time
+            for _ in 0..10 {
+                advance_block();
+            }
+            assert_eq!(contract.settle_recharge_only(2), Ok(()));
+            let ship = contract.get_ship(2).unwrap();
+            assert_eq!((ship.shield, ship.health), (50, 100));
+
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+            let ship = contract.get_ship(2).unwrap();
+            assert_eq!((ship.shield, ship.health), (20, 100));
+
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+            let ship = contract.get_ship(2).unwrap();
+            assert_eq!((ship.shield, ship.health), (0, 90));
+
+            advance_block();
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+            let ship = contract.get_ship(2).unwrap();
+            assert_eq!((ship.shield, ship.health), (0, 65));
+            let regenerated = ink::env::test::recorded_events()
+                .filter(|event| {
+                    matches!(
+                        <Event as scale::Decode>::decode(&mut &event.data[..]),
+                        Ok(Event::ShieldRegenerated(_))
+                    )
+                })
+                .count();
+            assert_eq!(regenerated, 2);
+        }
+
+        #[ink::test]
+        fn friendly_fire_toggle() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            place_ship(&mut contract, 2, (1, 0));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(30, 1, 10))).is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+            assert_eq!(contract.get_ship(2).unwrap().health, 100);
+
+            assert_eq!(contract.set_friendly_fire(true), Ok(()));
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+            assert_eq!(contract.get_ship(2).unwrap().health, 70);
+        }
+
+        #[ink::test]
+        fn ship_names_are_unique() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            let name = String::from("Rocinante");
+            assert_eq!(contract.set_ship_name(1, name.clone()), Ok(()));
+            assert_eq!(contract.get_ship_by_name(name.clone()), Some(1));
+            assert_eq!(contract.set_ship_name(2, name.clone()), Err(Error::NameTaken));
+
+            // renaming frees the old name
+            assert_eq!(contract.set_ship_name(1, String::from("Canterbury")), Ok(()));
+            assert_eq!(contract.get_ship_by_name(String::from("Canterbury")), Some(1));
+            assert_eq!(contract.get_ship_by_name(name.clone()), None);
+            assert_eq!(contract.set_ship_name(2, name.clone()), Ok(()));
+            assert_eq!(contract.get_ship_by_name(name), Some(2));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.set_ship_name(1, String::from("Donnager")), Err(Error::NotShipOwner));
+        }
+
+        #[ink::test]
+        fn weapons_have_a_cooldown() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            let weapon = Weapon::new(30, 1, 10).with_cooldown(2);
+            assert!(ship.inventory.add_item(Item::Weapon(weapon)).is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Err(Error::WeaponOnCooldown));
+            advance_block();
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Err(Error::WeaponOnCooldown));
+            advance_block();
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+        }
+
+        #[ink::test]
+        fn cooldowns_survive_weapon_transfers() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            let weapon = Weapon::new(30, 1, 10).with_cooldown(2);
+            assert!(ship.inventory.add_item(Item::Weapon(weapon)).is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+            assert_eq!(contract.fire_weapon(1, 1, (1, 0)), Ok(()));
+
+            // hand the weapon over to the other ship right away
+            assert_eq!(contract.unequip_item(1, 1), Ok(()));
+            assert_eq!(contract.move_item(1, 1, true), Ok(()));
+            let item_id = contract.get_ship(1).unwrap().cargo.items()[0].id();
+            assert_eq!(contract.transfer_cargo(1, 2, item_id), Ok(()));
+            let item_id = contract.get_ship(2).unwrap().cargo.items()[0].id();
+            assert_eq!(contract.move_item(2, item_id, false), Ok(()));
+            let item_id = contract.get_ship(2).unwrap().inventory.items()[0].id();
+            assert_eq!(contract.equip_item(2, item_id), Ok(()));
+            assert_eq!(contract.fire_weapon(2, item_id, (1, 0)), Err(Error::WeaponOnCooldown));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.fire_weapon(2, item_id, (1, 0)), Ok(()));
+        }
+
+        #[ink::test]
+        fn remove_ship_id_works() {
+            let mut contract = Rareships::new();
+            for ship_id in 1..=3 {
+                assert_eq!(contract.spawn(ship_id), Ok(()));
+            }
+            contract.remove_ship_id(2);
+            assert_eq!(contract.get_ships(), vec![1, 3]);
+            // removing an unknown id is a no-op
+            contract.remove_ship_id(42);
+            assert_eq!(contract.get_ships(), vec![1, 3]);
+        }
+
+        #[ink::test]
+        fn salvage_destroyed_ship() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            let mut ship = contract.get_ship(2).unwrap();
+            ship.position = (2, 0);
+            for resource_type in [ResourceType::Iron, ResourceType::Gold] {
+                assert!(ship
+                    .cargo
+                    .add_item(Item::Resource(Resource::new(resource_type, 10)))
+                    .is_ok());
+            }
+            contract.ships.insert(2, &ship);
+
+            let salvage = Order::Salvage(((2, 0), 1));
+            assert_eq!(contract.order(1, salvage.clone(), None), Err(Error::WreckNotFound));
+            assert_eq!(contract.destroy_ship(2), Ok(()));
+            assert!(contract.get_ship(2).is_none());
+            assert_eq!(contract.get_ships(), vec![1]);
+
+            // only room for one of the two stacks
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo = Inventory::new(1);
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 10000, 2)), None), Ok(()));
+            assert_eq!(contract.order(1, salvage.clone(), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_cargo(1).map(|items| items.len()), Some(1));
+            assert_eq!(contract.wrecks.get((2, 0)).map(|wreck| wreck.items().len()), Some(1));
+
+            // the rest is picked up once there is room
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo.set_max_size(2);
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.order(1, salvage, None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_cargo(1).map(|items| items.len()), Some(2));
+            assert!(!contract.wrecks.contains((2, 0)));
+        }
+
+        #[ink::test]
+        fn vitals_are_clamped_to_max() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.energy = 5000;
+            ship.health = 500;
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.energy, ship.max_energy);
+            assert_eq!(ship.health, ship.max_health);
+        }
+
+        #[ink::test]
+        fn spawn_requires_payment() {
+            let mut contract = Rareships::new_with_config(100, 100, 50);
+            assert_eq!(contract.get_spawn_cost(), 50);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(49);
+            assert_eq!(contract.spawn(1), Err(Error::InsufficientPayment));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(50);
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(contract.get_collected_fees(), 100);
+
+            assert_eq!(contract.set_spawn_cost(0), Ok(()));
+            assert_eq!(contract.spawn(3), Ok(()));
+            assert_eq!(contract.get_collected_fees(), 100);
+
+            let bob = accounts().bob;
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(bob);
+            assert_eq!(contract.set_spawn_cost(10), Err(Error::NotAuthorized));
+            assert_eq!(contract.withdraw_fees(), Err(Error::NotAuthorized));
+
+            let admin = accounts().alice;
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(admin);
+            let contract_account = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract_account, 1000);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(admin).unwrap();
+            assert_eq!(contract.withdraw_fees(), Ok(100));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(admin).unwrap();
+            assert_eq!(after - before, 100);
+            assert_eq!(contract.get_collected_fees(), 0);
+        }
+
+        #[ink::test]
+        fn spawn_respects_ship_limit() {
+            let mut contract = Rareships::new();
+            let limit = contract.get_ship_limit();
+            for ship_id in 1..=limit {
+                assert_eq!(contract.spawn(ship_id), Ok(()));
+            }
+            assert_eq!(contract.spawn(limit + 1), Err(Error::ShipLimitReached));
+
+            // other accounts have their own limit
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.spawn(limit + 1), Ok(()));
+
+            // destroyed ships free up a slot
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            assert_eq!(contract.destroy_ship(1), Ok(()));
+            assert_eq!(contract.spawn(limit + 2), Ok(()));
+        }
+
+        #[ink::test]
+        fn mining_into_full_cargo_fails() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            // fill the cargo with a full stack of another resource
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo = Inventory::new(1);
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Copper, 64)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Err(Error::NotEnoughCargoSpace));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.energy, 1000);
+            assert_eq!(ship.orders.len(), 1);
+        }
+
+        #[ink::test]
+        fn mined_totals_accumulate() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.get_mined_totals(1), Vec::new());
+            let rate = contract.get_planet(1).unwrap().get_mining_rate(&ResourceType::Iron) as u64;
+
+            for _ in 0..2 {
+                assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+            }
+            assert_eq!(contract.get_mined_totals(1), vec![(ResourceType::Iron, 2 * rate)]);
+        }
+
+        #[ink::test]
+        fn total_mined_counts_each_resource() {
+            let mut contract = Rareships::new();
+            contract
+                .planets
+                .insert(1, &Planet::new(1, PlanetLevel::Fortress, (0, 0)));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let planet = contract.get_planet(1).unwrap();
+            let iron = planet.get_mining_rate(&ResourceType::Iron) as u64;
+            let copper = planet.get_mining_rate(&ResourceType::Copper) as u64;
+
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_total_mined(ResourceType::Iron), iron);
+            assert_eq!(contract.get_total_mined(ResourceType::Copper), 0);
+
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Copper, 2)), None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_total_mined(ResourceType::Iron), iron);
+            assert_eq!(contract.get_total_mined(ResourceType::Copper), 2 * copper);
+            assert_eq!(contract.get_total_mined(ResourceType::Gold), 0);
+        }
+
+        #[ink::test]
+        fn dropping_mining_pays_out_the_elapsed_blocks() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 10)), None), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(3), None), Ok(()));
+            for _ in 0..5 {
+                advance_block();
+            }
+
+            assert_eq!(contract.drop_order(1, 0), Ok(()));
+            // half of the duration mined at a rate of 1, half of the energy charged
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Iron), 5);
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.energy, 1000 - 5 * 100);
+            assert_eq!(ship.orders, vec![(Order::Idle(3), Some(5), None)]);
+
+            let events: Vec<Event> = ink::env::test::recorded_events()
+                .filter_map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).ok())
+                .collect();
+            assert!(events
+                .iter()
+                .any(|event| matches!(event, Event::ResourceMined(ResourceMined { quantity: 5, .. }))));
+            // the order was dropped, not completed
+            assert!(events
+                .iter()
+                .any(|event| matches!(event, Event::OrderDropped(OrderDropped { ship_id: 1, .. }))));
+            assert!(!events.iter().any(|event| matches!(event, Event::OrderCompleted(_))));
+
+            // dropping it before it ran yields nothing
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 10)), None), Ok(()));
+            assert_eq!(contract.drop_order(1, 1), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Iron), 5);
+        }
+
+        #[ink::test]
+        fn dropping_the_front_order_starts_the_next() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(10), None), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(5), None), Ok(()));
+            assert_eq!(contract.drop_order(1, 0), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().orders, vec![(Order::Idle(5), Some(0), None)]);
+
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            for _ in 0..4 {
+                advance_block();
+            }
+            assert_eq!(contract.settle(1), Ok(()));
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+        }
+
+        #[ink::test]
+        fn mine_all_mines_every_resource() {
+            let mut contract = Rareships::new();
+            contract
+                .planets
+                .insert(1, &Planet::new(1, PlanetLevel::Fortress, (0, 0)));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            // the silver stack only has room for one more
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo = Inventory::new(3);
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Silver, 63)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.order(1, Order::MineAll((1, 2)), None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert!(ship.orders.is_empty());
+            // 3 resources charged, the recharge of two blocks is added before
+            assert_eq!(ship.energy, 1000 - 3 * 200);
+            let mut mined = contract.get_mined_totals(1);
+            mined.sort_by_key(|(_, total)| *total);
+            assert_eq!(
+                mined,
+                vec![
+                    (ResourceType::Silver, 1),
+                    (ResourceType::Iron, 2),
+                    (ResourceType::Copper, 2),
+                ]
+            );
+            assert_eq!(ship.cargo.items().len(), 3);
+        }
+
+        #[ink::test]
+        fn mining_foreign_planet_pays_fee() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().bob, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 5)), None), Ok(()));
+            for _ in 0..5 {
+                advance_block();
+            }
+            assert_eq!(contract.settle(1), Ok(()));
+
+            // 20% of the 5 iron go to the planet owner
+            assert_eq!(contract.get_mined_totals(1), vec![(ResourceType::Iron, 4)]);
+            let royalty = contract.get_planet(1).unwrap().get_inventory().items();
+            assert_eq!(royalty.len(), 1);
+            assert!(matches!(&royalty[0], Item::Resource(r) if r.quantity() == 1));
+        }
+
+        #[ink::test]
+        fn queued_moves_dont_block_mining() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 2)), None), Ok(()));
+            let order = Order::Move((Direction::East, 1000, 1));
+            assert_eq!(contract.order(1, order.clone(), Some(1)), Ok(()));
+
+            for _ in 0..2 {
+                advance_block();
+            }
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Iron), 2);
+            assert_eq!(contract.get_ship(1).unwrap().orders[0].0, order);
+        }
+
+        #[ink::test]
+        fn small_yields_pay_the_fee_too() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().bob, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 2)), None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+
+            // 20% of 2 iron rounds up to 1
+            assert_eq!(contract.get_mined_totals(1), vec![(ResourceType::Iron, 1)]);
+            let royalty = contract.get_planet(1).unwrap().get_inventory();
+            assert_eq!(royalty.count_resource(&ResourceType::Iron), 1);
+        }
+
+        #[ink::test]
+        fn resource_mined_topics() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+
+            let mined = ink::env::test::recorded_events()
+                .find_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::ResourceMined(mined)) => Some((mined, event.topics.len())),
+                        _ => None,
+                    }
+                })
+                .unwrap();
+            assert_eq!(mined.0.planet_id, 1);
+            assert_eq!(mined.0.resource_type, ResourceType::Iron);
+            // the event signature plus ship, planet and resource type
+            assert_eq!(mined.1, 4);
+        }
+
+        #[ink::test]
+        fn set_mining_rate_works() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.set_mining_rate(2, ResourceType::Iron, 5), Err(Error::PlanetNotFound));
+            assert_eq!(contract.set_mining_rate(1, ResourceType::Gold, 5), Err(Error::ResourceNotFound));
+            assert_eq!(contract.set_mining_rate(1, ResourceType::Iron, 5), Ok(()));
+            assert_eq!(contract.get_planet(1).unwrap().get_mining_rate(&ResourceType::Iron), 5);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.set_mining_rate(1, ResourceType::Iron, 1), Err(Error::NotAuthorized));
+        }
+
+        #[ink::test]
+        fn get_planet_yields_pairs_rates() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_planet_yields(1), None);
+            let mut planet = Planet::new(1, PlanetLevel::Advanced, (0, 0));
+            assert!(planet.set_mining_rate(&ResourceType::Copper, 3));
+            contract.planets.insert(1, &planet);
+            assert_eq!(
+                contract.get_planet_yields(1),
+                Some(vec![(ResourceType::Iron, 1), (ResourceType::Copper, 3)])
+            );
+        }
+
+        #[ink::test]
+        fn mining_levels_up_the_ship() {
+            assert_eq!(xp_for_level(1), 0);
+            assert_eq!(xp_for_level(2), 100);
+            assert_eq!(xp_for_level(3), 400);
+
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.set_mining_rate(1, ResourceType::Iron, 50), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!((ship.xp, ship.level), (50, 1));
+
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!((ship.xp, ship.level), (100, 2));
+            assert_eq!(ship.max_health, 100 + LEVEL_HEALTH_BONUS);
+            assert_eq!(ship.max_energy, 1000 + LEVEL_ENERGY_BONUS);
+        }
+
+        #[ink::test]
+        fn orders_on_removed_planets_are_invalidated() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 2)), None), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(1), None), Ok(()));
+            contract.planets.remove(1);
+            contract.tile_planet.remove((0, 0));
+
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.orders.len(), 1);
+            assert_eq!(ship.orders[0].0, Order::Idle(1));
+            assert!(contract.get_cargo(1).unwrap().is_empty());
+
+            let invalidated = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::OrderInvalidated(invalidated)) => Some((invalidated.order, invalidated.reason)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                invalidated,
+                vec![(Order::Mine((1, ResourceType::Iron, 2)), Error::PlanetNotFound)]
+            );
+        }
+
+        #[ink::test]
+        fn asteroids_are_mined_until_depleted() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (2, 2), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn_asteroid_field((2, 2), 1, ResourceType::Gold, 5), Ok(()));
+            assert_eq!(contract.get_asteroid((2, 2)), None);
+            assert_eq!(contract.get_asteroid((3, 2)), Some((ResourceType::Gold, 5)));
+            assert_eq!(contract.get_asteroid((4, 2)), None);
+
+            assert_eq!(contract.spawn(1), Ok(()));
+            let order = Order::MineAsteroid(((1, 0), 2));
+            assert_eq!(
+                contract.order(1, Order::MineAsteroid(((5, 5), 2)), None),
+                Err(Error::AsteroidNotFound)
+            );
+            assert_eq!(contract.spawn_asteroid_field((1, 0), 0, ResourceType::Gold, 5), Ok(()));
+            assert_eq!(contract.order(1, order.clone(), None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Gold), 4);
+            assert_eq!(contract.get_asteroid((1, 0)), Some((ResourceType::Gold, 1)));
+
+            // only the last unit is left
+            assert_eq!(contract.order(1, order, None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Gold), 5);
+            assert_eq!(contract.get_asteroid((1, 0)), None);
+            let depleted = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::AsteroidDepleted(depleted)) => Some(depleted.position),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(depleted, vec![(1, 0)]);
+        }
+
+        #[ink::test]
+        fn asteroids_out_of_range_invalidate_the_order() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn_asteroid_field((1, 0), 0, ResourceType::Gold, 5), Ok(()));
+            let order = Order::MineAsteroid(((1, 0), 2));
+            assert_eq!(contract.order(1, order.clone(), None), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(1), None), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.position = (10, 10);
+            contract.ships.insert(1, &ship);
+
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.orders.len(), 1);
+            assert_eq!(ship.orders[0].0, Order::Idle(1));
+            assert_eq!(contract.get_asteroid((1, 0)), Some((ResourceType::Gold, 5)));
+
+            let invalidated = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::OrderInvalidated(invalidated)) => Some((invalidated.order, invalidated.reason)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(invalidated, vec![(order, Error::TargetOutOfRange)]);
+        }
+
+        #[ink::test]
+        fn mine_energy_cost_is_configurable() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_mine_energy_cost(), 100);
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mine = |contract: &mut Rareships| {
+                let mut ship = contract.get_ship(1).unwrap();
+                ship.energy = 500;
+                contract.ships.insert(1, &ship);
+                assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 2)), None), Ok(()));
+                advance_block();
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+                contract.get_ship_energy(1).unwrap()
+            };
+            let default_energy = mine(&mut contract);
+            assert_eq!(contract.set_mine_energy_cost(30), Ok(()));
+            assert_eq!(mine(&mut contract) - default_energy, 2 * (100 - 30));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.set_mine_energy_cost(1), Err(Error::NotAuthorized));
+        }
+
+        #[ink::test]
+        fn mine_and_deposit_fills_owned_planet() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let deposit = Order::MineAndDeposit((1, ResourceType::Iron, 1));
+
+            // unowned planets don't take deposits
+            assert_eq!(contract.order(1, deposit.clone(), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_cargo(1).map(|items| items.len()), Some(1));
+
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().alice, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.order(1, deposit.clone(), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let inventory = contract.get_planet(1).unwrap().get_inventory().items();
+            assert!(matches!(&inventory[..], [Item::Resource(r)] if r.quantity() == 1));
+
+            // a full planet inventory falls back to the cargo
+            let mut planet = contract.get_planet(1).unwrap();
+            assert!(planet
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 63)))
+                .is_ok());
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.order(1, deposit, None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let cargo = contract.get_cargo(1).unwrap();
+            assert!(matches!(&cargo[..], [Item::Resource(r)] if r.quantity() == 2));
+        }
+
+        #[ink::test]
+        fn claim_and_abandon_planet() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.mint_planet(2, (1, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.claim_planet(1, 2), Err(Error::ShipNotAtPlanet));
+            assert_eq!(contract.abandon_planet(1), Err(Error::NotPlanetOwner));
+            assert_eq!(contract.claim_planet(1, 1), Ok(()));
+            assert_eq!(contract.get_planet(1).unwrap().get_owner(), Some(accounts().alice));
+            assert_eq!(contract.claim_planet(1, 1), Err(Error::PlanetAlreadyOwned));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.abandon_planet(1), Err(Error::NotPlanetOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            assert_eq!(contract.abandon_planet(1), Ok(()));
+            assert_eq!(contract.get_planet(1).unwrap().get_owner(), None);
+            assert_eq!(contract.claim_planet(1, 1), Ok(()));
+        }
+
+        #[ink::test]
+        fn move_by_cube_delta() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            place_ship(&mut contract, 1, (5, 5));
+
+            assert_eq!(contract.order(1, Order::MoveBy(((2, -1, -1), 10000)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (7, 4));
+            assert!(ship.orders.is_empty());
+            assert!(ship.energy < ship.max_energy);
+
+            // wraps around the map
+            place_ship(&mut contract, 1, (0, 0));
+            assert_eq!(contract.order(1, Order::MoveBy(((-1, 0, 1), 10000)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let (max_x, _) = contract.map_bounds;
+            assert_eq!(contract.get_ship(1).unwrap().position, (max_x - 1, 0));
+        }
+
+        #[ink::test]
+        fn move_by_rejects_invalid_delta() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(
+                contract.order(1, Order::MoveBy(((1, 1, 1), 1000)), None),
+                Err(Error::InvalidOrder)
+            );
+            assert_eq!(
+                contract.order(1, Order::MoveBy(((0, 0, 0), 1000)), None),
+                Err(Error::InvalidOrder)
+            );
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+        }
+
+        #[ink::test]
+        fn planet_claims_expire() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.set_claim_expiry(5), Ok(()));
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.claim_planet(1, 1), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.spawn(2), Ok(()));
+
+            for _ in 0..4 {
+                advance_block();
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            assert_eq!(contract.refresh_claim(1), Ok(()));
+            for _ in 0..5 {
+                advance_block();
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.claim_planet(2, 1), Err(Error::PlanetAlreadyOwned));
+
+            // one block too late
+            advance_block();
+            assert_eq!(contract.claim_planet(2, 1), Ok(()));
+            assert_eq!(contract.get_planet(1).unwrap().get_owner(), Some(accounts().bob));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            assert_eq!(contract.refresh_claim(1), Err(Error::NotPlanetOwner));
+        }
+
+        #[ink::test]
+        fn expired_claims_grant_no_ownership() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.set_claim_expiry(2), Ok(()));
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.claim_planet(1, 1), Ok(()));
+            assert_eq!(contract.dock(1, 1), Ok(()));
+            assert_eq!(contract.get_planet_owner(1), Ok(Some(accounts().alice)));
+
+            for _ in 0..3 {
+                advance_block();
+            }
+            assert_eq!(contract.get_planet_owner(1), Ok(None));
+            // no owner's recharge bonus either
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.energy = 100;
+            ship.last_recharge = contract.get_current_block();
+            contract.ships.insert(1, &ship);
+            advance_block();
+            assert_eq!(contract.preview_recharge(1), Some(100 + ship.recharge_rate));
+            assert_eq!(contract.set_home_planet(1, 1), Err(Error::NotPlanetOwner));
+
+            // huge expiries don't overflow
+            assert_eq!(contract.set_claim_expiry(Block::MAX), Ok(()));
+            assert_eq!(contract.claim_planet(1, 1), Ok(()));
+            assert_eq!(contract.get_planet_owner(1), Ok(Some(accounts().alice)));
+            assert_eq!(contract.set_home_planet(1, 1), Ok(()));
+        }
+
+        #[ink::test]
+        fn mining_requires_ship_at_planet() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (5, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            let mine = Order::Mine((1, ResourceType::Iron, 1));
+            assert_eq!(contract.order(1, mine.clone(), None), Err(Error::ShipNotAtPlanet));
+
+            // a queued move may bring the ship to the planet
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 5000, 5)), None), Ok(()));
+            assert_eq!(contract.order(1, mine, None), Ok(()));
+        }
+
+        #[ink::test]
+        fn mining_from_adjacent_tile() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (1, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.mint_planet(2, (2, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            let far = Order::Mine((2, ResourceType::Iron, 1));
+            assert_eq!(contract.order(1, far, None), Err(Error::ShipNotAtPlanet));
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+            assert_eq!(contract.get_cargo(1).map(|items| items.len()), Some(1));
+        }
+
+        #[ink::test]
+        fn slow_movement_keeps_fractional_progress() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            // 300 milli-tiles per block
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 300, 100)), None), Ok(()));
+            for _ in 0..10 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+            }
+            // 10 blocks * 300 milli-tiles = 3 tiles
+            assert_eq!(contract.get_ship(1).unwrap().position, (3, 0));
+        }
+
+        #[ink::test]
+        fn idle_order_delays_next_order() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(2), None), Ok(()));
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 1000, 1)), None), Ok(()));
+
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().orders.len(), 2);
+
+            // idle is over after two blocks, the move starts at that block
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (1, 0));
+            assert!(ship.orders.is_empty());
+        }
+
+        #[ink::test]
+        fn patrol_flips_between_waypoints() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let order = Order::Patrol(((2, 0), (0, 0), 1000));
+            assert_eq!(contract.order(1, order, None), Ok(()));
+
+            for _ in 0..2 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+            }
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (2, 0));
+            assert_eq!(ship.orders[0].0, Order::Patrol(((0, 0), (2, 0), 1000)));
+
+            for _ in 0..2 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+            }
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (0, 0));
+            assert_eq!(ship.orders[0].0, Order::Patrol(((2, 0), (0, 0), 1000)));
+        }
+
+        #[ink::test]
+        fn running_out_of_energy_stalls_the_move() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.energy = 340;
+            contract.ships.insert(1, &ship);
+
+            // 100 energy per tile at max speed, the recharge of the block adds 10
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 10000, 10)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (3, 0));
+            assert_eq!(ship.energy, 50);
+            assert_eq!(ship.orders[0].0, Order::Move((Direction::East, 10000, 7)));
+
+            let stalled = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::ShipStalled(stalled)) => Some(stalled.remaining_distance),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(stalled, vec![7]);
+        }
+
+        #[ink::test]
+        fn validate_order_matches_order() {
+            let mut contract = Rareships::new();
+            let move_order = Order::Move((Direction::East, 1000, 3));
+            assert_eq!(contract.validate_order(1, move_order.clone()), Err(Error::ShipNotFound));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            let invalid = [
+                (Order::Move((Direction::East, 20000, 3)), Error::InvalidOrder),
+                (Order::Move((Direction::East, 1000, 0)), Error::InvalidOrder),
+                (Order::Mine((1, ResourceType::Iron, 1)), Error::PlanetNotFound),
+                (Order::Idle(0), Error::InvalidOrder),
+            ];
+            for (order, error) in invalid {
+                assert_eq!(contract.validate_order(1, order.clone()), Err(error));
+                assert!(contract.order(1, order, None).is_err());
+            }
+
+            assert_eq!(contract.validate_order(1, move_order.clone()), Ok(()));
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+            for _ in 0..MAX_ORDERS {
+                assert_eq!(contract.order(1, move_order.clone(), None), Ok(()));
+            }
+            assert_eq!(contract.validate_order(1, move_order), Err(Error::TooManyOrders));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.validate_order(1, Order::Idle(1)), Err(Error::NotShipOwner));
+        }
+
+        #[ink::test]
+        fn order_count_follows_the_queue() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_ship_order_count(1), 0);
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.get_ship_order_count(1), 0);
+            for duration in 1..=3 {
+                assert_eq!(contract.order(1, Order::Idle(duration), None), Ok(()));
+            }
+            assert_eq!(contract.get_ship_order_count(1), 3);
+            assert_eq!(contract.drop_order(1, 2), Ok(()));
+            assert_eq!(contract.get_ship_order_count(1), 2);
+        }
+
+        #[ink::test]
+        fn submit_orders_is_atomic() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (3, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            let invalid = vec![
+                Order::Move((Direction::East, 1000, 3)),
+                Order::Idle(0),
+            ];
+            assert_eq!(contract.submit_orders(1, invalid), Err(Error::InvalidOrder));
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+
+            let too_many = vec![Order::Idle(1); MAX_ORDERS + 1];
+            assert_eq!(contract.submit_orders(1, too_many), Err(Error::TooManyOrders));
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+
+            // the mine order is valid because of the move before it
+            let plan = vec![
+                Order::Move((Direction::East, 1000, 3)),
+                Order::Mine((1, ResourceType::Iron, 1)),
+            ];
+            assert_eq!(contract.submit_orders(1, plan), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().orders.len(), 2);
+        }
+
+        #[ink::test]
+        fn docked_ships_cant_move() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.mint_planet(2, (3, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.undock(1), Err(Error::InvalidOrder));
+            assert_eq!(contract.dock(1, 2), Err(Error::ShipNotAtPlanet));
+            assert_eq!(contract.dock(1, 3), Err(Error::PlanetNotFound));
+            assert_eq!(contract.dock(1, 1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().docked_at, Some(1));
+
+            let move_order = Order::Move((Direction::East, 1000, 3));
+            assert_eq!(contract.order(1, move_order.clone(), None), Err(Error::InvalidOrder));
+            let repeat = Order::Repeat((Box::new(Order::Patrol(((0, 0), (3, 0), 1000))), 0));
+            assert_eq!(contract.order(1, repeat, None), Err(Error::InvalidOrder));
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 1)), None), Ok(()));
+
+            assert_eq!(contract.undock(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().docked_at, None);
+            assert_eq!(contract.order(1, move_order, None), Ok(()));
+            // the queued move would take the ship away
+            assert_eq!(contract.dock(1, 1), Err(Error::InvalidOrder));
+        }
+
+        #[ink::test]
+        fn settling_without_progress_pays_no_bounty() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(contract.order(1, Order::Idle(1), Some(5)), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            // the order is scheduled for later
+            let bob = accounts().bob;
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(bob);
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_pending_bounty(1), 30);
+            assert_eq!(contract.get_keeper_bounty(bob), 0);
+
+            for _ in 0..5 {
+                advance_block();
+            }
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_pending_bounty(1), 0);
+            assert_eq!(contract.get_keeper_bounty(bob), 30);
+        }
+
+        #[ink::test]
+        fn scuttling_refunds_the_pending_bounty() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(contract.order(1, Order::Idle(5), None), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            assert_eq!(contract.scuttle_ship(1), Ok(()));
+            assert_eq!(contract.get_pending_bounty(1), 0);
+            assert_eq!(contract.get_keeper_bounty(accounts().alice), 30);
+        }
+
+        #[ink::test]
+        fn keepers_earn_the_settlement_bounty() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(contract.order(1, Order::Idle(1), None), Ok(()));
+            assert_eq!(contract.submit_orders(1, vec![Order::Idle(1)]), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(contract.get_pending_bounty(1), 60);
+
+            // the owner settling doesn't pay out
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_pending_bounty(1), 60);
+
+            let bob = accounts().bob;
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(bob);
+            assert_eq!(contract.claim_bounty(), Err(Error::NoBounty));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_pending_bounty(1), 0);
+            assert_eq!(contract.get_keeper_bounty(bob), 60);
+
+            let contract_account = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract_account, 1000);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(bob).unwrap();
+            assert_eq!(contract.claim_bounty(), Ok(60));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(bob).unwrap();
+            assert_eq!(after - before, 60);
+            assert_eq!(contract.get_keeper_bounty(bob), 0);
+        }
+
+        #[ink::test]
+        fn return_home_moves_to_home_planet() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::ReturnHome(2000), None), Err(Error::InvalidOrder));
+
+            assert_eq!(contract.dock(1, 1), Ok(()));
+            assert_eq!(contract.set_home_planet(1, 1), Err(Error::NotPlanetOwner));
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().alice, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.set_home_planet(1, 1), Ok(()));
+            assert_eq!(contract.undock(1), Ok(()));
+
+            place_ship(&mut contract, 1, (20, 3));
+            assert_eq!(contract.order(1, Order::ReturnHome(2000), None), Ok(()));
+            // the home planet's gravity slows the whole way down
+            for _ in 0..40 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+            }
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (0, 0));
+            assert!(ship.orders.is_empty());
+        }
+
+        #[ink::test]
+        fn follow_keeps_trailing_distance() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            assert_eq!(contract.order(1, Order::Follow((1, 0)), None), Err(Error::InvalidOrder));
+            assert_eq!(contract.order(1, Order::Follow((3, 0)), None), Err(Error::ShipNotFound));
+
+            let mut target = contract.get_ship(2).unwrap();
+            target.position = (12, 0);
+            contract.ships.insert(2, &target);
+
+            assert_eq!(contract.order(1, Order::Follow((2, 5)), None), Ok(()));
+            for _ in 0..3 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+            }
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (7, 0));
+            assert_eq!(ship.orders.len(), 1);
+
+            // the order completes once the target is gone
+            contract.ships.remove(2);
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+        }
+
+        #[ink::test]
+        fn transfer_cargo_works() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 10)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.transfer_cargo(1, 1, 1), Err(Error::InvalidOrder));
+            assert_eq!(contract.transfer_cargo(1, 2, 7), Err(Error::ItemNotFound));
+            assert_eq!(contract.transfer_cargo(1, 2, 1), Ok(()));
+            assert_eq!(contract.transfer_cargo(1, 2, 1), Err(Error::ItemNotFound));
+
+            // nothing moves if the receiver is full
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo = Inventory::new(0);
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.transfer_cargo(2, 1, 1), Err(Error::NotEnoughCargoSpace));
+            assert!(contract.get_ship(2).unwrap().cargo.remove_item(1).is_some());
+
+            let mut ship = contract.get_ship(2).unwrap();
+            ship.position = (1, 0);
+            contract.ships.insert(2, &ship);
+            assert_eq!(contract.transfer_cargo(2, 1, 1), Err(Error::ShipsNotColocated));
+        }
+
+        #[ink::test]
+        fn transfer_energy_is_clamped() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            let mut ship = contract.get_ship(2).unwrap();
+            ship.energy = 800;
+            contract.ships.insert(2, &ship);
+
+            // the receiver can only take 200 more
+            assert_eq!(contract.transfer_energy(1, 2, 500), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().energy, 800);
+            assert_eq!(contract.get_ship(2).unwrap().energy, 1000);
+        }
+
+        #[ink::test]
+        fn recharge_is_faster_at_owned_planet() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().alice, 0);
+            contract.planets.insert(1, &planet);
+
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            for (ship_id, position) in [(1, (0, 0)), (2, (5, 0))] {
+                let mut ship = contract.get_ship(ship_id).unwrap();
+                ship.position = position;
+                ship.energy = 500;
+                contract.ships.insert(ship_id, &ship);
+            }
+
+            for _ in 0..5 {
+                advance_block();
+            }
+            assert_eq!(contract.settle_recharge_only(1), Ok(()));
+            assert_eq!(contract.settle_recharge_only(2), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().energy, 600);
+            assert_eq!(contract.get_ship(2).unwrap().energy, 550);
+        }
+
+        #[ink::test]
+        fn equip_item_works() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Armor(Armor::new(5))).is_ok());
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(10, 2, 5))).is_ok());
+            assert!(ship
+                .inventory
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 1)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+            assert_eq!(contract.equip_item(1, 1), Err(Error::InvalidOrder));
+            assert_eq!(contract.equip_item(1, 2), Ok(()));
+            assert_eq!(contract.equip_item(1, 3), Err(Error::InvalidOrder));
+            assert_eq!(contract.equip_item(1, 4), Err(Error::ItemNotFound));
+            assert_eq!(contract.get_ship(1).unwrap().equipped, vec![1, 2]);
+
+            assert_eq!(contract.unequip_item(1, 1), Ok(()));
+            assert_eq!(contract.unequip_item(1, 1), Err(Error::ItemNotFound));
+            assert_eq!(contract.get_ship(1).unwrap().equipped, vec![2]);
+        }
+
+        #[ink::test]
+        fn upgrade_speed_consumes_resources() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.upgrade_speed(1), Err(Error::NotEnoughResources));
+
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 40)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.upgrade_speed(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().max_speed, 11000);
+            // only 8 iron left
+            assert_eq!(contract.upgrade_speed(1), Err(Error::NotEnoughResources));
+        }
+
+        #[ink::test]
+        fn upgrade_energy_and_recharge_rate() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Copper, 50)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.upgrade_energy_capacity(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.max_energy, 1100);
+            // the new capacity isn't filled up
+            assert_eq!(ship.energy, 1000);
+
+            // 34 copper left
+            assert_eq!(contract.upgrade_recharge_rate(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().recharge_rate, 12);
+            assert_eq!(contract.upgrade_energy_capacity(1), Err(Error::NotEnoughResources));
+        }
+
+        #[ink::test]
+        fn upgrade_cargo_grows_storage() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 64)))
+                .is_ok());
+            for _ in 1..32 {
+                assert!(ship.cargo.add_item(Item::Armor(Armor::new(1))).is_ok());
+            }
+            assert!(ship.cargo.add_item(Item::Armor(Armor::new(1))).is_err());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.upgrade_cargo(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.max_cargo_size, 40);
+            assert!(ship.cargo.add_item(Item::Armor(Armor::new(1))).is_ok());
+        }
+
+        #[ink::test]
+        fn split_stack_works() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo = Inventory::new(2);
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 64)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.split_stack(1, 1, 65), Err(Error::NotEnoughResources));
+            assert_eq!(contract.split_stack(1, 2, 1), Err(Error::ItemNotFound));
+            assert_eq!(contract.split_stack(1, 1, 32), Ok(()));
+            assert!(contract.get_ship(1).unwrap().cargo.get_item(2).is_some());
+            assert_eq!(contract.split_stack(1, 1, 33), Err(Error::NotEnoughResources));
+
+            // both slots are in use now
+            assert_eq!(contract.split_stack(1, 1, 16), Err(Error::NotEnoughInventorySpace));
+        }
+
+        #[ink::test]
+        fn move_item_between_holds() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.inventory = Inventory::new(1);
+            assert!(ship.cargo.add_item(Item::Armor(Armor::new(1))).is_ok());
+            assert!(ship.cargo.add_item(Item::Armor(Armor::new(2))).is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.move_item(1, 1, false), Ok(()));
+            // the inventory is full, the item stays in the cargo
+            assert_eq!(contract.move_item(1, 2, false), Err(Error::NotEnoughInventorySpace));
+            let ship = contract.get_ship(1).unwrap();
+            assert!(ship.cargo.get_item(2).is_some());
+            assert!(ship.inventory.get_item(1).is_some());
+
+            // equipped items can't be moved
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+            assert_eq!(contract.move_item(1, 1, true), Err(Error::InvalidOrder));
+
+            // the event tells the id the item got in the other hold
+            assert_eq!(contract.unequip_item(1, 1), Ok(()));
+            assert_eq!(contract.move_item(1, 1, true), Ok(()));
+            let moved = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::ItemMoved(moved)) => Some((moved.item_id, moved.new_item_id)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(moved, vec![(1, 1), (1, 3)]);
+            assert!(contract.get_ship(1).unwrap().cargo.get_item(3).is_some());
+        }
+
+        #[ink::test]
+        fn get_inventory_and_cargo() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_cargo(1), None);
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Armor(Armor::new(1))).is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.get_inventory(1).map(|items| items.len()), Some(1));
+            assert_eq!(contract.get_cargo(1), Some(Vec::new()));
+        }
+
+        #[ink::test]
+        fn ship_net_worth_sums_inventory_and_cargo() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.ship_net_worth(1), 0);
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.ship_net_worth(1), 0);
+
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(10, 2, 5))).is_ok());
+            assert!(ship.inventory.add_item(Item::Armor(Armor::new(4))).is_ok());
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 10)))
+                .is_ok());
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Uranium, 2)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            // weapon 10*2+2*5, armor 4*3, iron 10*1, uranium 2*25
+            assert_eq!(contract.ship_net_worth(1), 30 + 12 + 10 + 50);
+        }
+
+        #[ink::test]
+        fn leaderboard_ranks_by_net_worth() {
+            let mut contract = Rareships::new();
+            for (ship_id, gold) in [(1, 5), (2, 20), (3, 10)] {
+                assert_eq!(contract.spawn(ship_id), Ok(()));
+                let mut ship = contract.get_ship(ship_id).unwrap();
+                assert!(ship
+                    .cargo
+                    .add_item(Item::Resource(Resource::new(ResourceType::Gold, gold)))
+                    .is_ok());
+                contract.ships.insert(ship_id, &ship);
+            }
+            assert_eq!(contract.get_leaderboard(5), vec![(2, 200), (3, 100), (1, 50)]);
+            assert_eq!(contract.get_leaderboard(2), vec![(2, 200), (3, 100)]);
+            assert_eq!(contract.get_leaderboard(0), vec![]);
+        }
+
+        #[ink::test]
+        fn wormholes_teleport_arriving_ships() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (2, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.mint_planet(2, (5, 5), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.link_wormholes(1, 1), Err(Error::InvalidOrder));
+            assert_eq!(contract.link_wormholes(1, 3), Err(Error::PlanetNotFound));
+            assert_eq!(contract.link_wormholes(1, 2), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 10000, 2)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship_position(1), Some((5, 5)));
+            assert_eq!(contract.tile_ships.get((2, 0)), None);
+            assert_eq!(contract.tile_ships.get((5, 5)), Some(vec![1]));
+
+            let teleports = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::ShipTeleported(teleport)) => Some((teleport.from, teleport.to)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(teleports, vec![((2, 0), (5, 5))]);
+
+            // and back through the other end
+            assert_eq!(contract.order(1, Order::MoveBy(((1, 0, -1), 10000)), None), Ok(()));
+            assert_eq!(contract.order(1, Order::MoveBy(((-1, 0, 1), 10000)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship_position(1), Some((2, 0)));
+        }
+
+        #[ink::test]
+        fn admin_set_position() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 1000, 5)), None), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(3), None), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.admin_set_position(1, (3, 4)), Err(Error::NotAuthorized));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            let (max_x, max_y) = contract.map_bounds;
+            assert_eq!(contract.admin_set_position(1, (max_x, max_y)), Err(Error::InvalidOrder));
+            assert_eq!(contract.admin_set_position(1, (3, 4)), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (3, 4));
+            assert_eq!(ship.orders.len(), 1);
+            assert_eq!(ship.orders[0].0, Order::Idle(3));
+            assert_eq!(contract.tile_ships.get((0, 0)), None);
+            assert_eq!(contract.tile_ships.get((3, 4)), Some(vec![1]));
+
+            // relocated ships are undocked
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            assert_eq!(contract.dock(2, 1), Ok(()));
+            assert_eq!(contract.admin_set_position(2, (0, 0)), Ok(()));
+            assert_eq!(contract.get_ship(2).unwrap().docked_at, Some(1));
+            assert_eq!(contract.admin_set_position(2, (5, 5)), Ok(()));
+            assert_eq!(contract.get_ship(2).unwrap().docked_at, None);
+        }
+
+        #[ink::test]
+        fn scuttled_cargo_can_be_salvaged() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Silver, 10)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.spawn(2), Ok(()));
+            assert_eq!(contract.scuttle_ship(1), Err(Error::NotShipOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            assert_eq!(contract.scuttle_ship(1), Ok(()));
+            assert!(contract.get_ship(1).is_none());
+            assert_eq!(contract.get_ships(), vec![2]);
+            assert_eq!(contract.tile_ships.get((0, 0)), Some(vec![2]));
+            assert_eq!(contract.owner_ships.get(accounts().alice), Some(vec![]));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.order(2, Order::Salvage(((0, 0), 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(2), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 2, ResourceType::Silver), 10);
+            assert!(!contract.wrecks.contains((0, 0)));
+        }
+
+        #[ink::test]
+        fn ships_can_be_sold() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.buy_ship(1), Err(Error::NotForSale));
+            assert_eq!(contract.list_ship_for_sale(1, 100), Ok(()));
+            assert_eq!(contract.get_listing(1), Some((accounts().alice, 100)));
+
+            let contract_account = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract_account, 1000);
+            let seller_before =
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts().alice).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.cancel_listing(1), Err(Error::NotShipOwner));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(99);
+            assert_eq!(contract.buy_ship(1), Err(Error::InsufficientPayment));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(contract.buy_ship(1), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            assert_eq!(contract.get_ship(1).unwrap().owner, accounts().bob);
+            assert_eq!(contract.get_listing(1), None);
+            assert_eq!(contract.owner_ships.get(accounts().bob), Some(vec![1]));
+            assert_eq!(contract.owner_ships.get(accounts().alice), Some(vec![]));
+            let seller_after =
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts().alice).unwrap();
+            assert_eq!(seller_after - seller_before, 100);
+        }
+
+        #[ink::test]
+        fn ship_listings_can_be_cancelled() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.cancel_listing(1), Err(Error::NotForSale));
+            assert_eq!(contract.list_ship_for_sale(1, 100), Ok(()));
+            assert_eq!(contract.cancel_listing(1), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(contract.buy_ship(1), Err(Error::NotForSale));
+            assert_eq!(contract.get_ship(1).unwrap().owner, accounts().alice);
+        }
+
+        #[ink::test]
+        fn transfers_clear_the_listing() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.list_ship_for_sale(1, 100), Ok(()));
+            assert_eq!(contract.transfer_ship(1, accounts().bob), Ok(()));
+            assert_eq!(contract.get_listing(1), None);
+
+            // the old listing doesn't come back with the ship
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.transfer_ship(1, accounts().alice), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(contract.buy_ship(1), Err(Error::NotForSale));
+            assert_eq!(contract.get_ship(1).unwrap().owner, accounts().alice);
+        }
+
+        #[ink::test]
+        fn planets_store_deposited_resources() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Gold, 20)))
+                .is_ok());
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Gold, 20)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+            let item_id = contract.get_cargo(1).unwrap()[0].id();
+
+            assert_eq!(contract.deposit_to_planet(1, 1, item_id), Err(Error::ShipNotAtPlanet));
+            assert_eq!(contract.dock(1, 1), Ok(()));
+            assert_eq!(contract.deposit_to_planet(1, 1, item_id), Err(Error::NotPlanetOwner));
+            assert_eq!(contract.claim_planet(1, 1), Ok(()));
+            assert_eq!(contract.deposit_to_planet(1, 1, item_id), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Gold), 8);
+            let stored = contract.get_planet(1).unwrap().get_inventory().items();
+            assert_eq!(stored.len(), 1);
+
+            // a basic planet has room for a single stack
+            let other_id = contract.get_cargo(1).unwrap()[0].id();
+            assert_eq!(
+                contract.deposit_to_planet(1, 1, other_id),
+                Err(Error::NotEnoughInventorySpace)
+            );
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Gold), 8);
+
+            assert_eq!(contract.collect_planet_resources(1, 1, stored[0].id()), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Gold), 40);
+            assert!(contract.get_planet(1).unwrap().get_inventory().items().is_empty());
+        }
+
+        #[ink::test]
+        fn mint_planet_at_each_level() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.mint_planet(2, (1, 0), PlanetLevel::Advanced), Ok(()));
+            assert_eq!(contract.mint_planet(3, (2, 0), PlanetLevel::Fortress), Ok(()));
+            assert_eq!(contract.mint_basic_planet(4, (3, 0)), Ok(()));
+
+            let resources = |id| contract.get_planet(id).unwrap().get_resources();
+            assert_eq!(resources(1), vec![ResourceType::Iron]);
+            assert_eq!(resources(2), vec![ResourceType::Iron, ResourceType::Copper]);
+            assert_eq!(
+                resources(3),
+                vec![ResourceType::Iron, ResourceType::Copper, ResourceType::Silver]
+            );
+            assert_eq!(contract.get_planet(2).unwrap().get_level(), PlanetLevel::Advanced);
+            assert_eq!(contract.get_planet(4).unwrap().get_level(), PlanetLevel::Basic);
+
+            // planets off the map can't be reached
+            let (max_x, max_y) = contract.map_bounds;
+            assert_eq!(contract.mint_planet(5, (-1, 0), PlanetLevel::Basic), Err(Error::OutOfBounds));
+            assert_eq!(contract.mint_basic_planet(5, (max_x, max_y)), Err(Error::OutOfBounds));
+            assert_eq!(contract.get_planet(5), None);
+        }
+
+        #[ink::test]
+        fn mint_planets_in_one_batch() {
+            let mut contract = Rareships::new();
+            let batch = vec![
+                (1, (0, 0), PlanetLevel::Basic),
+                (2, (5, 5), PlanetLevel::Advanced),
+                (3, (9, 2), PlanetLevel::Fortress),
+            ];
+            assert_eq!(contract.mint_planets(batch), Ok(()));
+            assert_eq!(contract.get_planet(2).unwrap().get_position(), (5, 5));
+            assert_eq!(contract.get_planet(3).unwrap().get_level(), PlanetLevel::Fortress);
+            assert_eq!(contract.get_stats().planet_count, 3);
+            assert_eq!(ink::env::test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        fn mint_planets_rejects_the_whole_batch() {
+            let mut contract = Rareships::new();
+            let batch = vec![
+                (1, (0, 0), PlanetLevel::Basic),
+                (2, (5, 5), PlanetLevel::Basic),
+                (1, (7, 7), PlanetLevel::Basic),
+            ];
+            assert_eq!(contract.mint_planets(batch), Err(Error::PlanetAlreadyExists));
+            assert_eq!(contract.get_planet(1), None);
+            assert_eq!(contract.get_planet(2), None);
+            assert_eq!(contract.get_stats().planet_count, 0);
+
+            let batch = vec![(1, (0, 0), PlanetLevel::Basic), (2, (-1, 5), PlanetLevel::Basic)];
+            assert_eq!(contract.mint_planets(batch), Err(Error::OutOfBounds));
+            assert_eq!(contract.get_planet(1), None);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            let batch = vec![(1, (0, 0), PlanetLevel::Basic)];
+            assert_eq!(contract.mint_planets(batch), Err(Error::NotAuthorized));
+        }
+
+        #[ink::test]
+        fn version_matches_the_code() {
+            let contract = Rareships::new();
+            assert_eq!(contract.version(), VERSION);
+            assert_eq!(contract.get_storage_version(), contract.version());
+        }
+
+        #[ink::test]
+        fn upgrades_are_admin_only() {
+            let mut contract = Rareships::new();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.set_code(Hash::from([1; 32])), Err(Error::NotAuthorized));
+            assert_eq!(contract.migrate(), Err(Error::NotAuthorized));
+        }
+
+        #[ink::test]
+        fn migrate_records_the_code_version() {
+            let mut contract = Rareships::new();
+            contract.storage_version = String::from("0.0.1");
+            assert_eq!(contract.migrate(), Ok(()));
+            assert_eq!(contract.get_storage_version(), VERSION);
+        }
+
+        #[ink::test]
+        fn preview_recharge_matches_settlement() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.preview_recharge(1), None);
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.energy = 100;
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.preview_recharge(1), Some(100));
+
+            for _ in 0..3 {
+                advance_block();
+            }
+            let preview = contract.preview_recharge(1);
+            assert_eq!(preview, Some(100 + 3 * ship.recharge_rate));
+            assert_eq!(contract.get_ship(1).unwrap().energy, 100);
+            assert_eq!(contract.settle_recharge_only(1), Ok(()));
+            assert_eq!(Some(contract.get_ship(1).unwrap().energy), preview);
+
+            // the preview is capped at the max energy
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.energy = ship.max_energy - 1;
+            contract.ships.insert(1, &ship);
+            advance_block();
+            assert_eq!(contract.preview_recharge(1), Some(ship.max_energy));
+        }
+
+        #[ink::test]
+        fn ship_resource_count_sums_cargo_stacks() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_ship_resource_count(1, ResourceType::Iron), 0);
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            for quantity in [64, 10] {
+                assert!(ship
+                    .cargo
+                    .add_item(Item::Resource(Resource::new(ResourceType::Iron, quantity)))
+                    .is_ok());
+            }
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.get_cargo(1).unwrap().len(), 2);
+            assert_eq!(contract.get_ship_resource_count(1, ResourceType::Iron), 74);
+            assert_eq!(contract.get_ship_resource_count(1, ResourceType::Gold), 0);
+        }
+
+        #[ink::test]
+        fn planet_owner_query() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_planet_owner(1), Err(Error::PlanetNotFound));
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.get_planet_owner(1), Ok(None));
+
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().bob, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.get_planet_owner(1), Ok(Some(accounts().bob)));
+        }
+
+        #[ink::test]
+        fn planet_inventory_lists_deposits() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_planet_inventory(1), None);
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.get_planet_inventory(1), Some(vec![]));
+
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Copper, 12)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.claim_planet(1, 1), Ok(()));
+            assert_eq!(contract.dock(1, 1), Ok(()));
+            let item_id = contract.get_cargo(1).unwrap()[0].id();
+            assert_eq!(contract.deposit_to_planet(1, 1, item_id), Ok(()));
+
+            let inventory = contract.get_planet_inventory(1).unwrap();
+            assert!(matches!(
+                &inventory[..],
+                [Item::Resource(r)] if r.resource_type() == ResourceType::Copper && r.quantity() == 12
+            ));
+        }
+
+        fn cargo_quantity(contract: &Rareships, ship_id: ShipId, resource_type: ResourceType) -> u32 {
+            contract.get_ship_resource_count(ship_id, resource_type)
+        }
+
+        fn setup_trade(contract: &mut Rareships) -> u32 {
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 10)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            assert_eq!(contract.spawn(2), Ok(()));
+            let mut ship = contract.get_ship(2).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Copper, 10)))
+                .is_ok());
+            contract.ships.insert(2, &ship);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            let offer = (ResourceType::Iron, 10);
+            let request = (ResourceType::Copper, 5);
+            assert_eq!(
+                contract.propose_trade(1, offer.clone(), request.clone()),
+                Err(Error::InvalidOrder)
+            );
+            assert_eq!(contract.dock(1, 1), Ok(()));
+            let trade_id = contract.propose_trade(1, offer, request).unwrap();
+            assert_eq!(cargo_quantity(contract, 1, ResourceType::Iron), 0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().bob);
+            trade_id
+        }
+
+        #[ink::test]
+        fn destroyed_proposers_leave_the_offer_in_the_wreck() {
+            let mut contract = Rareships::new();
+            let trade_id = setup_trade(&mut contract);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            assert_eq!(contract.scuttle_ship(1), Ok(()));
+
+            assert_eq!(contract.get_trade(trade_id), None);
+            assert_eq!(contract.ship_trades.get(1), None);
+            let wreck = contract.wrecks.get((0, 0)).unwrap();
+            assert_eq!(wreck.count_resource(&ResourceType::Iron), 10);
+        }
+
+        #[ink::test]
+        fn offers_larger_than_a_stack_fit_into_the_wreck() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 128)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.dock(1, 1), Ok(()));
+            let offer = (ResourceType::Iron, 128);
+            assert!(contract.propose_trade(1, offer, (ResourceType::Copper, 5)).is_ok());
+
+            assert_eq!(contract.scuttle_ship(1), Ok(()));
+            let wreck = contract.wrecks.get((0, 0)).unwrap();
+            assert_eq!(wreck.count_resource(&ResourceType::Iron), 128);
+        }
+
+        #[ink::test]
+        fn trade_swaps_resources() {
+            let mut contract = Rareships::new();
+            let trade_id = setup_trade(&mut contract);
+            assert_eq!(contract.accept_trade(trade_id, 1), Err(Error::InvalidOrder));
+            assert_eq!(contract.accept_trade(trade_id, 2), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Copper), 5);
+            assert_eq!(cargo_quantity(&contract, 2, ResourceType::Iron), 10);
+            assert_eq!(cargo_quantity(&contract, 2, ResourceType::Copper), 5);
+            assert_eq!(contract.get_trade(trade_id), None);
+            assert_eq!(contract.accept_trade(trade_id, 2), Err(Error::TradeNotFound));
+        }
+
+        #[ink::test]
+        fn trade_is_reverted_without_cargo_space() {
+            let mut contract = Rareships::new();
+            let trade_id = setup_trade(&mut contract);
+            // a single slot, still taken by the copper that is left
+            let mut ship = contract.get_ship(2).unwrap();
+            ship.cargo = Inventory::new(1);
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Copper, 10)))
+                .is_ok());
+            contract.ships.insert(2, &ship);
+
+            assert_eq!(contract.accept_trade(trade_id, 2), Err(Error::NotEnoughCargoSpace));
+            assert_eq!(cargo_quantity(&contract, 2, ResourceType::Copper), 10);
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Copper), 0);
+            assert!(contract.get_trade(trade_id).is_some());
+
+            // the proposer can take the offer back
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts().alice);
+            assert_eq!(contract.cancel_trade(trade_id), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Iron), 10);
+        }
+
+        #[ink::test]
+        fn stats_are_counted() {
+            let mut contract = Rareships::new();
+            let stats = contract.get_stats();
+            assert_eq!((stats.ship_count, stats.planet_count), (0, 0));
+            assert_eq!(stats.admin, accounts().alice);
+
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            assert_eq!(contract.spawn(2), Err(Error::ShipAlreadyExists));
+            assert_eq!(contract.mint_planet(1, (3, 3), PlanetLevel::Basic), Ok(()));
+            let stats = contract.get_stats();
+            assert_eq!((stats.ship_count, stats.planet_count), (2, 1));
+
+            assert_eq!(contract.destroy_ship(2), Ok(()));
+            assert_eq!(contract.get_stats().ship_count, 1);
+        }
 
-            // save updated dynamics
-            self.ships.insert(ship_id, &ship);
+        #[ink::test]
+        fn preview_move_cost() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.preview_move_cost(1, 1000, 1), Err(Error::ShipNotFound));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let max_speed = contract.get_ship(1).unwrap().max_speed;
+            assert_eq!(contract.preview_move_cost(1, max_speed, 3), Ok(300));
+            assert_eq!(contract.preview_move_cost(1, max_speed / 2, 3), Ok(75));
+            assert_eq!(contract.preview_move_cost(1, max_speed + 1, 3), Err(Error::InvalidOrder));
+            assert_eq!(contract.preview_move_cost(1, max_speed, 0), Err(Error::InvalidOrder));
+            assert_eq!(contract.preview_move_cost(1, max_speed, i32::MAX), Ok(u32::MAX));
+        }
 
-            Ok(())
+        #[ink::test]
+        fn order_progress() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_order_progress(1), None);
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.get_order_progress(1), None);
+
+            let order = Order::Move((Direction::East, 1000, 2));
+            assert_eq!(contract.order(1, order.clone(), None), Ok(()));
+            assert_eq!(contract.get_order_progress(1), Some((order.clone(), 0, 2)));
+            advance_block();
+            assert_eq!(contract.get_order_progress(1), Some((order, 1, 2)));
+
+            let order = Order::Idle(4);
+            assert_eq!(contract.drop_order(1, 0), Ok(()));
+            assert_eq!(contract.order(1, order.clone(), None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.get_order_progress(1), Some((order, 2, 4)));
         }
 
-        #[ink(message)]
-        pub fn get_ships(&self) -> Vec<ShipId> {
-            self.ship_ids.get_or_default()
+        #[ink::test]
+        fn estimate_completion_of_moves_and_mining() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.estimate_completion(1), None);
+
+            // 3 tiles at 400 millitiles per block take ceil(7.5) blocks
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 400, 3)), None), Ok(()));
+            assert_eq!(contract.estimate_completion(1), Some(8));
+            advance_block();
+            assert_eq!(contract.estimate_completion(1), Some(8));
+
+            // the mining starts at block 1
+            assert_eq!(contract.drop_order(1, 0), Ok(()));
+            assert_eq!(contract.order(1, Order::Mine((1, ResourceType::Iron, 5)), None), Ok(()));
+            assert_eq!(contract.estimate_completion(1), Some(6));
         }
 
-        #[ink(message)]
-        pub fn get_ship(&self, ship_id: ShipId) -> Option<Ship> {
-            self.ships.get(ship_id)
+        #[ink::test]
+        fn estimate_completion_counts_partial_tiles() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 400, 3)), None), Ok(()));
+            assert_eq!(contract.estimate_completion(1), Some(8));
+
+            // settled in the middle of the first and the second tile
+            for expected_position in [(0, 0), (1, 0)] {
+                advance_block();
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+                assert_eq!(contract.get_ship_position(1), Some(expected_position));
+                assert_eq!(contract.estimate_completion(1), Some(8));
+            }
+            for _ in 0..4 {
+                advance_block();
+            }
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship_position(1), Some((3, 0)));
+
+            // moves that take forever
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 1, i32::MAX)), None), Ok(()));
+            assert_eq!(contract.estimate_completion(1), Some(u32::MAX));
+            assert!(contract.get_order_progress(1).is_some());
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.orders[0].0 = Order::Move((Direction::East, 0, 3));
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.estimate_completion(1), None);
         }
 
-        #[ink(message)]
-        pub fn get_planet(&self, planet_id: PlanetId) -> Option<Planet> {
-            self.planets.get(planet_id)
+        #[ink::test]
+        fn slow_ships_advance_every_block() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.get_ship_position_millis(1), Some((0, 0)));
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 300, 2)), None), Ok(()));
+            for expected in [300, 600, 900, 1200] {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+                assert_eq!(contract.get_ship_position_millis(1), Some((expected, 0)));
+            }
+            assert_eq!(contract.get_ship_position(1), Some((1, 0)));
+
+            // the progress counts towards the wrapped neighbor as well
+            let (max_x, _) = contract.map_bounds;
+            place_ship(&mut contract, 1, (0, 0));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.move_progress_millis = 0;
+            ship.orders = Vec::new();
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.order(1, Order::Move((Direction::West, 500, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship_position_millis(1), Some((-500, 0)));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship_position_millis(1), Some(((max_x - 1) * 1000, 0)));
         }
 
-        pub fn settle_ship(&mut self, ship_id: ShipId) -> Result<(), Error> {
-            // get ship dynamic and static data
-            let mut ship = self.ships.get(ship_id).ok_or(Error::ShipNotFound)?;
+        #[ink::test]
+        fn ship_accessors_read_fields() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(7), Ok(()));
+            assert_eq!(contract.set_ship_name(7, String::from("Nostromo")), Ok(()));
+            assert_eq!(contract.order(7, Order::Idle(3), None), Ok(()));
+            place_ship(&mut contract, 7, (4, 5));
+            let ship = contract.get_ship(7).unwrap();
 
-            // recharge energy
-            self.settle_recharge(&mut ship)?;
+            assert_eq!(ship.id(), 7);
+            assert_eq!(ship.name(), "Nostromo");
+            assert_eq!(ship.owner(), accounts().alice);
+            assert_eq!(ship.position(), (4, 5));
+            assert_eq!(ship.energy(), 1000);
+            assert_eq!(ship.max_energy(), 1000);
+            assert_eq!(ship.health(), 100);
+            assert_eq!(ship.max_health(), 100);
+            assert_eq!(ship.orders().len(), 1);
+            assert_eq!(ship.orders()[0].0, Order::Idle(3));
+            assert!(ship.inventory().items().is_empty());
+            assert!(ship.cargo().items().is_empty());
+        }
 
-            // settle orders order
-            self.settle_top_order(&mut ship)?;
+        #[ink::test]
+        fn ship_summary_mirrors_ship() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_ship_summary(1), None);
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.set_ship_name(1, String::from("Rocinante")), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(5), None), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(5), None), Ok(()));
 
-            // save updated dynamics
-            self.ships.insert(ship_id, &ship);
-            Ok(())
+            let ship = contract.get_ship(1).unwrap();
+            let summary = contract.get_ship_summary(1).unwrap();
+            assert_eq!(summary.id, ship.id);
+            assert_eq!(summary.name, ship.name);
+            assert_eq!(summary.owner, ship.owner);
+            assert_eq!(summary.position, ship.position);
+            assert_eq!(summary.energy, ship.energy);
+            assert_eq!(summary.health, ship.health);
+            assert_eq!(summary.order_count, 2);
         }
 
-        fn settle_recharge(
-            &self,
-            ship: &mut Ship,
-        ) -> Result<(), Error> {
-            let block = self.env().block_number();
-            let elapsed = block - ship.last_recharge;
-            // self.debug(&format!("recharge: block: {} last: {} elapsed: {}", block, ship.last_recharge, elapsed));
-            if elapsed > 0 && ship.energy < ship.max_energy {
-                let amount = elapsed * ship.recharge_rate;
-                let mut new_energy = ship.energy + amount;
-                if new_energy > ship.max_energy {
-                    new_energy = ship.max_energy;
-                }
-                ship.energy = new_energy;
-                ship.last_recharge = block;
-                self.env().emit_event(ShipRecharged {
-                    ship_id: ship.id,
-                    new_energy: ship.energy,
-                });
-            }
-            Ok(())
+        #[ink::test]
+        fn get_ship_position_and_energy() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.get_ship_position(1), None);
+            assert_eq!(contract.get_ship_energy(1), None);
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.get_ship_position(1), Some((0, 0)));
+            assert_eq!(contract.get_ship_energy(1), Some(1000));
         }
 
-        fn settle_top_order(
-            &self,
-            ship: &mut Ship,
-        ) -> Result<(), Error> {
-            if ship.orders.is_empty() {
-                return Ok(());
+        #[ink::test]
+        fn planets_slow_down_movement() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (1, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            // ship 3 heads for a planet
+            assert_eq!(contract.mint_planet(2, (10, 20), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(3), Ok(()));
+            for (ship_id, y) in [(2, 10), (3, 20)] {
+                let mut ship = contract.get_ship(ship_id).unwrap();
+                ship.position = (0, y);
+                contract.ships.insert(ship_id, &ship);
             }
-            match ship.orders.first().ok_or(Error::InvalidOrder)? {
-                (Order::Move((direction, speed, distance)), Some(start)) => self.settle_movement(
-                    ship,
-                    direction.clone(),
-                    *speed,
-                    *distance,
-                    *start,
-                )?,
-                (Order::Mine((planet_id, resource_type, duration)), Some(start)) => self
-                    .settle_mining(
-                        ship,
-                        *planet_id,
-                        resource_type.clone(),
-                        *duration,
-                        *start,
-                    )?,
-                _ => return Err(Error::InvalidOrder),
-            };
-            Ok(())
-        }
 
-        fn settle_movement(
-            &self,
-            ship: &mut Ship,
-            direction: Direction,
-            speed: i32,    // milli tiles per block
-            distance: i32, // tiles
-            start: Block,  // block number
-        ) -> Result<(), Error> {
-            let block = self.env().block_number();
-            let elapsed = (block - start) as i32;
-            if elapsed == 0 || elapsed * speed < 1000 {
-                return Ok(());
+            for ship_id in [1, 2, 3] {
+                assert_eq!(contract.order(ship_id, Order::Move((Direction::East, 4000, 10)), None), Ok(()));
             }
-            let mut tiles_to_move = elapsed * speed / 1000;
-            if tiles_to_move > distance {
-                tiles_to_move = distance;
+            advance_block();
+            for ship_id in [1, 2, 3] {
+                assert_eq!(contract.settle(ship_id), Ok(()));
             }
-            if tiles_to_move <= 0 {
-                return Ok(());
+            assert_eq!(contract.get_ship_position(1), Some((2, 0)));
+            assert_eq!(contract.get_ship_position(2), Some((4, 10)));
+            assert_eq!(contract.get_ship_position(3), Some((2, 20)));
+        }
+
+        #[ink::test]
+        fn endless_moves_settle() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            place_ship(&mut contract, 1, (5, 5));
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 1000, i32::MAX)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship_position(1), Some((6, 5)));
+        }
+
+        #[ink::test]
+        fn occupied_tiles_block_movement() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            assert_eq!(contract.order(2, Order::Move((Direction::East, 10000, 3)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(2), Ok(()));
+            assert_eq!(contract.get_ship_position(2), Some((3, 0)));
+
+            // ship 1 stops next to ship 2 and keeps the rest of the move
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 10000, 3)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (2, 0));
+            assert_eq!(ship.energy, 1000 - 2 * 100);
+            assert_eq!(ship.orders[0].0, Order::Move((Direction::East, 10000, 1)));
+
+            // without collisions the ships can share the tile
+            assert_eq!(contract.set_collisions_enabled(false), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship_position(1), Some((3, 0)));
+        }
+
+        #[ink::test]
+        fn blocked_ships_stop_on_the_nearest_free_tile() {
+            let mut contract = Rareships::new();
+            for ship_id in 1..=3 {
+                assert_eq!(contract.spawn(ship_id), Ok(()));
             }
+            place_ship(&mut contract, 2, (3, 0));
+            place_ship(&mut contract, 3, (2, 0));
 
-            let cost = move_energy_per_tile(speed, ship.max_speed) as u32;
-            if (cost as i32) * tiles_to_move > ship.energy as i32 {
-                tiles_to_move = ship.energy as i32 / cost as i32;
+            // the tile in front of ship 2 is taken as well
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 10000, 3)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (1, 0));
+            assert_eq!(ship.energy, 1000 - 100);
+            assert_eq!(ship.orders[0].0, Order::Move((Direction::East, 10000, 2)));
+        }
+
+        #[ink::test]
+        fn every_movement_respects_occupied_tiles() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (2, 20), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.mint_planet(2, (5, 25), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.link_wormholes(1, 2), Ok(()));
+            for ship_id in 1..=6 {
+                assert_eq!(contract.spawn(ship_id), Ok(()));
             }
-            ship.energy -= cost * tiles_to_move as u32;
-            self.env().emit_event(EnergyUsed {
-                ship_id: ship.id,
-                new_energy: ship.energy,
-            });
+            place_ship(&mut contract, 2, (3, 0));
+            place_ship(&mut contract, 3, (0, 10));
+            place_ship(&mut contract, 4, (3, 10));
+            place_ship(&mut contract, 5, (0, 20));
+            place_ship(&mut contract, 6, (5, 25));
 
-            // update the position by moving in direction tiles_to_move times
-            let (q, r, s) = offset_coordinates_to_cube_coordinates(ship.position);
-            let (q, r, s) = match direction {
-                Direction::NorthWest => (q, r - tiles_to_move, s + tiles_to_move),
-                Direction::NorthEast => (q + tiles_to_move, r - tiles_to_move, s),
-                Direction::East => (q + tiles_to_move, r, s - tiles_to_move),
-                Direction::SouthEast => (q, r + tiles_to_move, s - tiles_to_move),
-                Direction::SouthWest => (q - tiles_to_move, r + tiles_to_move, s),
-                Direction::West => (q - tiles_to_move, r, s + tiles_to_move),
-            };
-            let (mut x, mut y) = cube_coordinates_to_offset_coordinates((q, r, s));
-            if x < 0 {
-                x = MAX_X + x;
+            assert_eq!(contract.order(1, Order::MoveBy(((3, 0, -3), 10000)), None), Ok(()));
+            let patrol = Order::Patrol(((3, 10), (0, 10), 10000));
+            assert_eq!(contract.order(3, patrol, None), Ok(()));
+            assert_eq!(contract.order(5, Order::Move((Direction::East, 10000, 2)), None), Ok(()));
+            advance_block();
+            for ship_id in [1, 3, 5] {
+                assert_eq!(contract.settle(ship_id), Ok(()));
             }
-            if y < 0 {
-                y = MAX_Y + y;
+            assert_eq!(contract.get_ship_position(1), Some((2, 0)));
+            assert_eq!(contract.get_ship(1).unwrap().orders[0].0, Order::MoveBy(((1, 0, -1), 10000)));
+            assert_eq!(contract.get_ship_position(3), Some((2, 10)));
+            // the wormhole exit is taken, the ship stays at the entrance
+            assert_eq!(contract.get_ship_position(5), Some((2, 20)));
+
+            let blocked = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::MovementBlocked(blocked)) => Some((blocked.ship_id, blocked.blocked_by)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(blocked, vec![(1, 2), (3, 4), (5, 6)]);
+        }
+
+        #[ink::test]
+        fn stalled_ships_next_to_others_dont_panic() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.energy = 10;
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 10000, 3)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (0, 0));
+            assert_eq!(ship.orders[0].0, Order::Move((Direction::East, 10000, 3)));
+        }
+
+        #[ink::test]
+        fn refine_iron_into_steel() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 10)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            assert_eq!(contract.refine(1, ResourceType::Copper, 3), Err(Error::InvalidOrder));
+            assert_eq!(contract.refine(1, ResourceType::Iron, 4), Err(Error::InvalidOrder));
+            assert_eq!(contract.refine(1, ResourceType::Iron, 12), Err(Error::NotEnoughResources));
+            assert_eq!(contract.refine(1, ResourceType::Iron, 9), Ok(()));
+
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.energy, 1000 - 3 * REFINE_ENERGY_PER_UNIT);
+            let cargo: Vec<(ResourceType, u32)> = ship
+                .cargo
+                .items()
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Resource(r) => Some((r.resource_type(), r.quantity())),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(cargo, vec![(ResourceType::Iron, 1), (ResourceType::Steel, 3)]);
+        }
+
+        #[ink::test]
+        fn refine_checks_the_cargo_space_first() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo = Inventory::new(1);
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 6)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            // the iron stack stays, so the steel has no slot
+            assert_eq!(contract.refine(1, ResourceType::Iron, 3), Err(Error::NotEnoughCargoSpace));
+            assert_eq!(contract.get_ship(1).unwrap().energy, 1000);
+            // refining all of it frees the slot
+            assert_eq!(contract.refine(1, ResourceType::Iron, 6), Ok(()));
+            assert_eq!(cargo_quantity(&contract, 1, ResourceType::Steel), 2);
+        }
+
+        #[ink::test]
+        fn cargo_weight_slows_down_movement() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            assert_eq!(contract.spawn(2), Ok(()));
+            for ship_id in [1, 2] {
+                let mut ship = contract.get_ship(ship_id).unwrap();
+                ship.cargo = Inventory::new(1);
+                contract.ships.insert(ship_id, &ship);
             }
-            ship.position = (x % MAX_X, y % MAX_Y);
+            let mut ship = contract.get_ship(2).unwrap();
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 64)))
+                .is_ok());
+            contract.ships.insert(2, &ship);
 
-            let rest = distance - tiles_to_move;
-            if rest == 0 {
-                // order finished
-                let order = ship.orders.remove(0).0;
-                if !ship.orders.is_empty() {
-                    ship.orders[0].1 = Some(block);
-                }
-                self.env().emit_event(OrderCompleted {
-                    ship_id: ship.id,
-                    order: order,
-                });
-            } else {
-                let order = Order::Move((direction, speed, rest));
-                ship.orders[0] = (order.clone(), Some(block));
-                self.env().emit_event(OrderUpdated {
-                    ship_id: ship.id,
-                    order: order,
-                });
+            for ship_id in [1, 2] {
+                assert_eq!(contract.order(ship_id, Order::Move((Direction::East, 1000, 10)), None), Ok(()));
             }
+            for _ in 0..4 {
+                advance_block();
+            }
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.settle(2), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().position, (4, 0));
+            assert_eq!(contract.get_ship(2).unwrap().position, (2, 0));
+        }
 
-            self.env().emit_event(ShipMoved {
-                ship_id: ship.id,
-                position: ship.position,
-                energy_cost: cost * tiles_to_move as u32,
-            });
+        #[ink::test]
+        fn order_expires_after_deadline() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let order = Order::Move((Direction::East, 1000, 10));
+            assert_eq!(contract.order_with_deadline(1, order, 2), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(5), None), Ok(()));
 
-            Ok(())
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().position, (1, 0));
+
+            // the move still gets the block up to its deadline
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (2, 0));
+            // the next order starts at the deadline
+            assert_eq!(ship.orders, vec![(Order::Idle(5), Some(2), None)]);
         }
 
-        fn settle_mining(
-            &self,
-            ship: &mut Ship,
-            planet_id: PlanetId,
-            resource_type: ResourceType,
-            duration: Block,
-            start: Block,
-        ) -> Result<(), Error> {
-            let block = self.env().block_number();
-            let elapsed = block - start;
-            if elapsed < duration {
-                // not enough time has passed
-                return Ok(());
-            }
-            let cost = mine_energy_per_block() * duration;
-            if cost > ship.energy {
-                // not enough energy
-                return Ok(());
+        #[ink::test]
+        fn late_settlements_dont_expire_completed_orders() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let order = Order::Move((Direction::East, 1000, 2));
+            assert_eq!(contract.order_with_deadline(1, order, 2), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(2), None), Ok(()));
+
+            // the move was done at the deadline, the idle order started right then
+            for _ in 0..5 {
+                advance_block();
             }
-            let planet = self.planets.get(&planet_id).ok_or(Error::PlanetNotFound)?;
-            if planet.get_position() != ship.position {
-                // ship is not on the planet
-                return Err(Error::ResourceNotFound);
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (2, 0));
+            assert_eq!(ship.orders.len(), 1);
+            assert_eq!(ship.orders[0].1, Some(2));
+            let expired = ink::env::test::recorded_events()
+                .filter_map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).ok())
+                .filter(|event| matches!(event, Event::OrderExpired(_)))
+                .count();
+            assert_eq!(expired, 0);
+        }
+
+        #[ink::test]
+        fn scheduled_order_waits_for_start_block() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let order = Order::Move((Direction::East, 1000, 1));
+            assert_eq!(contract.order(1, order, Some(3)), Ok(()));
+
+            for _ in 0..3 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
+                assert_eq!(contract.get_ship(1).unwrap().position, (0, 0));
             }
-            if let Some(owner) = planet.get_owner() {
-                if owner != ship.owner {
-                    // planet is not owned by the ship's owner
-                    return Err(Error::NotPlanetOwner);
-                }
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().position, (1, 0));
+        }
+
+        #[ink::test]
+        fn mine_until_full_stops_when_cargo_is_full() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (0, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            ship.cargo = Inventory::new(1);
+            assert!(ship
+                .cargo
+                .add_item(Item::Resource(Resource::new(ResourceType::Iron, 60)))
+                .is_ok());
+            contract.ships.insert(1, &ship);
+
+            let order = Order::MineUntilFull((1, ResourceType::Iron));
+            assert_eq!(contract.order(1, order, None), Ok(()));
+            for _ in 0..2 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
             }
-            if !planet.get_resources().contains(&resource_type) {
-                // planet does not have the resource
-                return Err(Error::ResourceNotFound);
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.orders.len(), 1);
+            assert_eq!(ship.cargo.resource_space(&ResourceType::Iron), 2);
+
+            for _ in 0..5 {
+                advance_block();
             }
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert!(ship.orders.is_empty());
+            assert_eq!(ship.cargo.resource_space(&ResourceType::Iron), 0);
+            // only the four mined blocks were paid for, plus seven blocks of recharge
+            assert_eq!(ship.energy, 670);
+        }
 
-            // extract the resource and put to the ship's inventory
-            ship.energy -= cost;
-            self.env().emit_event(EnergyUsed {
-                ship_id: ship.id,
-                new_energy: ship.energy,
-            });
-            let amount = planet.get_mining_rate(&resource_type) * duration as u32;
-            ship.cargo
-                .add_item(Item::Resource(Resource::new(resource_type.clone(), amount)))?;
-            self.env().emit_event(ResourceMined {
-                ship_id: ship.id,
-                planet_id: planet_id,
-                resource_type: resource_type.clone(),
-                quantity: amount,
-            });
+        #[ink::test]
+        fn repeat_runs_inner_order_n_times() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let nested = Order::Repeat((Box::new(Order::Repeat((Box::new(Order::Idle(1)), 2))), 2));
+            assert_eq!(contract.order(1, nested, None), Err(Error::InvalidOrder));
 
-            // order finished, remove it
-            let order = ship.orders.remove(0).0;
-            if !ship.orders.is_empty() {
-                ship.orders[0].1 = Some(block);
+            let order = Order::Repeat((Box::new(Order::Move((Direction::East, 1000, 1))), 3));
+            assert_eq!(contract.order(1, order, None), Ok(()));
+            for _ in 0..10 {
+                advance_block();
+                assert_eq!(contract.settle(1), Ok(()));
             }
-            self.env().emit_event(OrderCompleted {
-                ship_id: ship.id,
-                order: order,
-            });
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!(ship.position, (3, 0));
+            assert!(ship.orders.is_empty());
 
-            Ok(())
+            // the repeat keeps its second slot while orders queue up behind it
+            for _ in 0..MAX_ORDERS - 2 {
+                assert_eq!(contract.order(1, Order::Idle(1), None), Ok(()));
+            }
+            let order = Order::Repeat((Box::new(Order::Idle(1)), 0));
+            assert_eq!(contract.order(1, order, None), Ok(()));
+            assert_eq!(contract.order(1, Order::Idle(1), None), Err(Error::TooManyOrders));
         }
 
-        fn debug(&self, msg: &str) {
-            self.env().emit_event(DebugEvent {
-                value: msg.to_string(),
-            });
+        #[ink::test]
+        fn settle_and_get_ship_returns_fresh_state() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.spawn(1), Ok(()));
+            let order = Order::Move((Direction::East, 1000, 5));
+            assert_eq!(contract.order(1, order, None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.get_current_block(), 2);
+            assert_eq!(contract.get_ship(1).unwrap().position, (0, 0));
+            assert_eq!(contract.settle_and_get_ship(1).unwrap().position, (2, 0));
+            assert!(contract.settle_and_get_ship(2).is_err());
         }
-    }
 
-    fn offset_coordinates_to_cube_coordinates(c: (i32, i32)) -> (i32, i32, i32) {
-        let (col, row) = c;
-        let q = col - (row - (row & 1i32)) / 2;
-        let r = row;
-        (q, r, -q - r)
-    }
+        #[ink::test]
+        fn movement_wraps_at_configured_bounds() {
+            let mut contract = Rareships::new_with_map_bounds(100, 100);
+            assert_eq!(contract.get_map_bounds(), (100, 100));
+            assert_eq!(Rareships::new().get_map_bounds(), (10000, 10000));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let order = Order::Move((Direction::West, 1000, 2));
+            assert_eq!(contract.order(1, order, None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().position, (98, 0));
+        }
 
-    fn cube_coordinates_to_offset_coordinates(c: (i32, i32, i32)) -> (i32, i32) {
-        let (q, r, _) = c;
-        let col = q + (r - (r & 1i32)) / 2;
-        let row = r;
-        (col, row)
-    }
+        #[ink::test]
+        fn ship_moved_reports_wrapping() {
+            let mut contract = Rareships::new_with_map_bounds(100, 100);
+            assert_eq!(contract.spawn(1), Ok(()));
+            place_ship(&mut contract, 1, (98, 4));
+            let wrapped_moves = || -> Vec<bool> {
+                ink::env::test::recorded_events()
+                    .filter_map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).ok())
+                    .filter_map(|event| match event {
+                        Event::ShipMoved(moved) => Some(moved.wrapped),
+                        _ => None,
+                    })
+                    .collect()
+            };
 
-    fn move_energy_per_tile(speed: i32, max_speed: i32) -> i32 {
-        100 * speed / max_speed
-    }
+            // one tile east stays on the map
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 1000, 1)), None), Ok(()));
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(wrapped_moves(), vec![false]);
 
-    fn mine_energy_per_block() -> u32 {
-        100
-    }
+            // the next two cross the east edge
+            assert_eq!(contract.order(1, Order::Move((Direction::East, 1000, 2)), None), Ok(()));
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_ship(1).unwrap().position, (1, 4));
+            assert_eq!(wrapped_moves(), vec![false, true]);
+        }
 
-    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
-    /// module and test functions are marked with a `#[test]` attribute.
-    /// The below code is technically just normal Rust code.
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+        #[ink::test]
+        fn bombard_clears_planet_owner() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (1, 0), PlanetLevel::Basic), Ok(()));
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(30, 1, 10))).is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+
+            // unowned planets can't be bombarded
+            let order = Order::Bombard((1, 1));
+            assert_eq!(contract.order(1, order.clone(), None), Err(Error::InvalidOrder));
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().bob, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.order(1, Order::Bombard((1, 2)), None), Err(Error::ItemNotFound));
+            assert_eq!(contract.order(1, order, None), Ok(()));
+
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert_eq!(contract.get_planet(1).unwrap().get_health(), 40);
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let planet = contract.get_planet(1).unwrap();
+            assert_eq!(planet.get_owner(), None);
+            assert_eq!(planet.get_health(), 100);
+            assert!(contract.get_ship(1).unwrap().orders.is_empty());
+        }
 
         #[ink::test]
-        fn coordinates_calculation_works() {
-            let cases = vec![
-                (0, 0),
-                (1, 0),
-                (0, 1),
-                (1, 1),
-                (2, 1),
-                (1, 2),
-                (2, 2),
-                (3, 2),
-                (2, 3),
-                (3, 3),
-                (4, 3),
-                (3, 4),
-                (4, 4),
-                (5, 4),
-                (4, 5),
-                (5, 5),
-            ];
-            for c in cases {
-                let (q, r, s) = offset_coordinates_to_cube_coordinates(c);
-                let c2 = cube_coordinates_to_offset_coordinates((q, r, s));
-                assert_eq!(c, c2);
+        fn bombarded_planets_fire_back() {
+            let mut contract = Rareships::new();
+            contract
+                .planets
+                .insert(1, &Planet::new(1, PlanetLevel::Fortress, (1, 0)));
+            contract.tile_planet.insert((1, 0), &1);
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().bob, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(30, 1, 10))).is_ok());
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+            assert_eq!(contract.order(1, Order::Bombard((1, 1)), None), Ok(()));
+
+            // two shots, the shield that charged up meanwhile takes the first 10
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            let ship = contract.get_ship(1).unwrap();
+            assert_eq!((ship.shield, ship.health), (0, 60));
+            let defended = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::PlanetDefended(defended)) => Some(defended.damage),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(defended, vec![50]);
+
+            // the fortress outlasts the ship
+            for _ in 0..3 {
+                advance_block();
             }
+            assert_eq!(contract.settle(1), Ok(()));
+            assert!(contract.get_ship(1).is_none());
+            assert_eq!(contract.get_planet(1).unwrap().get_owner(), Some(accounts().bob));
+        }
+
+        #[ink::test]
+        fn ships_can_take_a_planet_with_their_last_shot() {
+            let mut contract = Rareships::new();
+            assert_eq!(contract.mint_planet(1, (1, 0), PlanetLevel::Basic), Ok(()));
+            let mut planet = contract.get_planet(1).unwrap();
+            planet.claim(accounts().bob, 0);
+            contract.planets.insert(1, &planet);
+            assert_eq!(contract.spawn(1), Ok(()));
+            let mut ship = contract.get_ship(1).unwrap();
+            assert!(ship.inventory.add_item(Item::Weapon(Weapon::new(60, 1, 10))).is_ok());
+            ship.health = 5;
+            ship.shield = 0;
+            ship.max_shield = 0;
+            contract.ships.insert(1, &ship);
+            assert_eq!(contract.equip_item(1, 1), Ok(()));
+            assert_eq!(contract.order(1, Order::Bombard((1, 1)), None), Ok(()));
+
+            advance_block();
+            advance_block();
+            assert_eq!(contract.settle(1), Ok(()));
+            assert!(contract.get_ship(1).is_none());
+            assert_eq!(contract.get_planet(1).unwrap().get_owner(), None);
+            let lost = ink::env::test::recorded_events()
+                .filter_map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::PlanetLost(lost)) => Some(lost.planet_id),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(lost, vec![1]);
+        }
+
+        // place_ship moves the ship to the position, keeping the tile index up to date
+        fn place_ship(contract: &mut Rareships, ship_id: ShipId, position: (i32, i32)) {
+            let mut ship = contract.get_ship(ship_id).unwrap();
+            contract.remove_tile_ship(ship.position, ship_id);
+            contract.add_tile_ship(position, ship_id);
+            ship.position = position;
+            contract.ships.insert(ship_id, &ship);
+        }
+
+        fn advance_block() {
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+        }
+
+        fn accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
         }
     }
 }
@@ -0,0 +1,356 @@
+// mechanics holds the pure game rules: hex coordinates, movement and energy formulas.
+// The contract loads and stores ships and emits events, the math lives here.
+use ink::prelude::vec::Vec;
+
+// cargo slows a ship down by up to this share of its speed
+const MAX_CARGO_SLOWDOWN_PERCENT: i32 = 50;
+
+// Directions are used to move the ship
+#[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub enum Direction {
+    NorthWest,
+    NorthEast,
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+}
+
+// xp_for_level is the experience needed to reach the level
+pub fn xp_for_level(level: u32) -> u32 {
+    let level = level.saturating_sub(1);
+    100u32.saturating_mul(level).saturating_mul(level)
+}
+
+pub fn offset_coordinates_to_cube_coordinates(c: (i32, i32)) -> (i32, i32, i32) {
+    let (col, row) = c;
+    let q = col - (row - (row & 1i32)) / 2;
+    let r = row;
+    (q, r, -q - r)
+}
+
+pub fn cube_coordinates_to_offset_coordinates(c: (i32, i32, i32)) -> (i32, i32) {
+    let (q, r, _) = c;
+    let col = q + (r - (r & 1i32)) / 2;
+    let row = r;
+    (col, row)
+}
+
+// move_in_direction moves tiles in direction, wrapping around the map bounds
+pub fn move_in_direction(
+    position: (i32, i32),
+    direction: &Direction,
+    tiles: i32,
+    bounds: (i32, i32),
+) -> (i32, i32) {
+    // moves can be longer than the map, so they may wrap several times. Any move of
+    // 2 * max_x * max_y tiles ends where it started, so longer moves are shortened first to
+    // keep the math in range.
+    let laps = 2 * bounds.0 as i64 * bounds.1 as i64;
+    let tiles = (tiles as i64).rem_euclid(laps) as i32;
+    wrap_position(unwrapped_move(position, direction, tiles), bounds)
+}
+
+// wrap_position maps a position off the map back onto it
+pub fn wrap_position(position: (i32, i32), bounds: (i32, i32)) -> (i32, i32) {
+    (position.0.rem_euclid(bounds.0), position.1.rem_euclid(bounds.1))
+}
+
+// unwrapped_move is the position the move ends at if the map had no edges
+fn unwrapped_move(position: (i32, i32), direction: &Direction, tiles: i32) -> (i32, i32) {
+    let (q, r, s) = offset_coordinates_to_cube_coordinates(position);
+    let (q, r, s) = match direction {
+        Direction::NorthWest => (q, r - tiles, s + tiles),
+        Direction::NorthEast => (q + tiles, r - tiles, s),
+        Direction::East => (q + tiles, r, s - tiles),
+        Direction::SouthEast => (q, r + tiles, s - tiles),
+        Direction::SouthWest => (q - tiles, r + tiles, s),
+        Direction::West => (q - tiles, r, s + tiles),
+    };
+    cube_coordinates_to_offset_coordinates((q, r, s))
+}
+
+// crosses_edge tells whether the move wraps around an edge of the map
+pub fn crosses_edge(
+    position: (i32, i32),
+    direction: &Direction,
+    tiles: i32,
+    bounds: (i32, i32),
+) -> bool {
+    !in_bounds(unwrapped_move(position, direction, tiles), bounds)
+}
+
+// step_wraps tells whether the step between two neighboring tiles wraps around an edge of the map
+pub fn step_wraps(from: (i32, i32), to: (i32, i32)) -> bool {
+    (to.0 - from.0).abs() > 1 || (to.1 - from.1).abs() > 1
+}
+
+// cube_direction returns the cube coordinate delta of one step in direction
+pub fn cube_direction(direction: &Direction) -> (i32, i32, i32) {
+    match direction {
+        Direction::NorthWest => (0, -1, 1),
+        Direction::NorthEast => (1, -1, 0),
+        Direction::East => (1, 0, -1),
+        Direction::SouthEast => (0, 1, -1),
+        Direction::SouthWest => (-1, 1, 0),
+        Direction::West => (-1, 0, 1),
+    }
+}
+
+pub fn cube_length(delta: (i32, i32, i32)) -> i32 {
+    (delta.0.abs() + delta.1.abs() + delta.2.abs()) / 2
+}
+
+// direction_along returns the direction of the step that shortens the delta the most
+pub fn direction_along(delta: (i32, i32, i32)) -> Direction {
+    [
+        Direction::NorthWest,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::West,
+    ]
+    .into_iter()
+    .min_by_key(|direction| {
+        let (uq, ur, us) = cube_direction(direction);
+        cube_length((delta.0 - uq, delta.1 - ur, delta.2 - us))
+    })
+    .unwrap_or(Direction::East)
+}
+
+// wrapped_offset returns the offset from a tile to a neighbor, taking the shorter way around the map
+pub fn wrapped_offset(from: (i32, i32), to: (i32, i32), bounds: (i32, i32)) -> (i32, i32) {
+    let unwrap = |delta: i32, max: i32| match delta {
+        delta if delta > 1 => delta - max,
+        delta if delta < -1 => delta + max,
+        delta => delta,
+    };
+    (unwrap(to.0 - from.0, bounds.0), unwrap(to.1 - from.1, bounds.1))
+}
+
+// tiles_in_range returns all tiles within radius of the position, including the position,
+// wrapping around the map bounds
+pub fn tiles_in_range(position: (i32, i32), radius: i32, bounds: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut tiles = Vec::new();
+    for x in position.0 - radius..=position.0 + radius {
+        for y in position.1 - radius..=position.1 + radius {
+            if hex_distance(position, (x, y)) > radius {
+                continue;
+            }
+            // a radius wider than the map would reach the same tile twice
+            let tile = wrap_position((x, y), bounds);
+            if !tiles.contains(&tile) {
+                tiles.push(tile);
+            }
+        }
+    }
+    tiles
+}
+
+pub fn in_bounds(position: (i32, i32), bounds: (i32, i32)) -> bool {
+    position.0 >= 0 && position.0 < bounds.0 && position.1 >= 0 && position.1 < bounds.1
+}
+
+pub fn hex_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    let (aq, ar, as_) = offset_coordinates_to_cube_coordinates(a);
+    let (bq, br, bs) = offset_coordinates_to_cube_coordinates(b);
+    ((aq - bq).abs() + (ar - br).abs() + (as_ - bs).abs()) / 2
+}
+
+// hex_neighbors returns the six tiles around position, clockwise from north west,
+// wrapping around the map bounds
+pub fn hex_neighbors(position: (i32, i32), bounds: (i32, i32)) -> [(i32, i32); 6] {
+    [
+        Direction::NorthWest,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::West,
+    ]
+    .map(|direction| move_in_direction(position, &direction, 1, bounds))
+}
+
+// step_towards returns the neighbor of position that is closest to target
+pub fn step_towards(position: (i32, i32), target: (i32, i32), bounds: (i32, i32)) -> (i32, i32) {
+    let mut best = position;
+    for candidate in hex_neighbors(position, bounds) {
+        if hex_distance(candidate, target) < hex_distance(best, target) {
+            best = candidate;
+        }
+    }
+    best
+}
+
+// direction_to picks the hex direction that points most directly from one tile to another,
+// going the short way around the map
+pub fn direction_to(from: (i32, i32), to: (i32, i32), bounds: (i32, i32)) -> Direction {
+    let nearest = |from: i32, to: i32, max: i32| {
+        let delta = (to - from).rem_euclid(max);
+        if delta > max / 2 {
+            from + delta - max
+        } else {
+            from + delta
+        }
+    };
+    let to = (nearest(from.0, to.0, bounds.0), nearest(from.1, to.1, bounds.1));
+    let (fq, fr, fs) = offset_coordinates_to_cube_coordinates(from);
+    let (tq, tr, ts) = offset_coordinates_to_cube_coordinates(to);
+    direction_along((tq - fq, tr - fr, ts - fs))
+}
+
+// loaded_speed reduces the speed linearly with the load, a full ship
+// still moves at (100 - MAX_CARGO_SLOWDOWN_PERCENT)% of the speed
+pub fn loaded_speed(speed: i32, weight: u32, max_weight: u32) -> i32 {
+    if max_weight == 0 {
+        return speed;
+    }
+    let load_percent = (weight.min(max_weight) * 100 / max_weight) as i32;
+    speed * (100 - load_percent * MAX_CARGO_SLOWDOWN_PERCENT / 100) / 100
+}
+
+// move_energy_per_tile grows with the square of the speed, so going at full speed
+// costs 100 per tile while half speed only costs 25. It's rounded up, so any
+// movement costs at least 1 per tile.
+pub fn move_energy_per_tile(speed: i32, max_speed: i32) -> i32 {
+    let (speed, max_speed) = (speed as i64, max_speed as i64);
+    let divisor = max_speed * max_speed;
+    ((100 * speed * speed + divisor - 1) / divisor) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: (i32, i32) = (100, 100);
+
+    #[test]
+    fn coordinates_calculation_works() {
+        let cases = vec![
+            (0, 0),
+            (1, 0),
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (1, 2),
+            (2, 2),
+            (3, 2),
+            (2, 3),
+            (3, 3),
+            (4, 3),
+            (3, 4),
+            (4, 4),
+            (5, 4),
+            (4, 5),
+            (5, 5),
+        ];
+        for c in cases {
+            let (q, r, s) = offset_coordinates_to_cube_coordinates(c);
+            let c2 = cube_coordinates_to_offset_coordinates((q, r, s));
+            assert_eq!(c, c2);
+        }
+    }
+
+    #[test]
+    fn moves_wrap_around_the_map() {
+        assert_eq!(move_in_direction((0, 0), &Direction::East, 3, BOUNDS), (3, 0));
+        assert_eq!(move_in_direction((0, 0), &Direction::West, 1, BOUNDS), (99, 0));
+        assert_eq!(move_in_direction((5, 0), &Direction::NorthEast, 1, BOUNDS), (5, 99));
+        for neighbor in hex_neighbors((4, 4), BOUNDS) {
+            assert_eq!(hex_distance((4, 4), neighbor), 1);
+            assert!(wrapped_offset((4, 4), neighbor, BOUNDS).0.abs() <= 1);
+        }
+        assert_eq!(wrapped_offset((0, 0), (99, 0), BOUNDS), (-1, 0));
+    }
+
+    #[test]
+    fn long_moves_wrap_several_times() {
+        assert_eq!(move_in_direction((0, 0), &Direction::West, 250, BOUNDS), (50, 0));
+        assert_eq!(move_in_direction((10, 4), &Direction::East, 390, BOUNDS), (0, 4));
+        assert_eq!(move_in_direction((5, 0), &Direction::SouthEast, 200, BOUNDS).1, 0);
+        let (x, y) = move_in_direction((5, 0), &Direction::NorthWest, 301, BOUNDS);
+        assert!(in_bounds((x, y), BOUNDS));
+        assert_eq!(y, 99);
+        assert_eq!(move_in_direction((5, 5), &Direction::East, i32::MAX, BOUNDS), (52, 5));
+        assert_eq!(move_in_direction((5, 5), &Direction::SouthEast, 20000, BOUNDS), (5, 5));
+    }
+
+    #[test]
+    fn moves_across_the_edge_are_detected() {
+        assert!(crosses_edge((99, 4), &Direction::East, 1, BOUNDS));
+        assert!(crosses_edge((4, 0), &Direction::NorthWest, 1, BOUNDS));
+        assert!(crosses_edge((0, 4), &Direction::West, 3, BOUNDS));
+        assert!(!crosses_edge((97, 4), &Direction::East, 2, BOUNDS));
+        assert!(!crosses_edge((4, 4), &Direction::SouthWest, 4, BOUNDS));
+        assert!(step_wraps((99, 4), (0, 4)));
+        assert!(step_wraps((4, 0), (4, 99)));
+        assert!(!step_wraps((4, 4), (5, 5)));
+    }
+
+    #[test]
+    fn steps_follow_the_shortest_path() {
+        assert_eq!(step_towards((0, 0), (3, 0), BOUNDS), (1, 0));
+        assert_eq!(step_towards((2, 2), (2, 2), BOUNDS), (2, 2));
+        assert_eq!(direction_along((2, 0, -2)), Direction::East);
+        assert_eq!(cube_length((2, -1, -1)), 2);
+        assert_eq!(tiles_in_range((5, 5), 1, BOUNDS).len(), 7);
+        let edge = tiles_in_range((0, 0), 1, BOUNDS);
+        assert_eq!(edge.len(), 7);
+        assert!(edge.contains(&(99, 0)));
+        assert!(edge.iter().all(|tile| in_bounds(*tile, BOUNDS)));
+        assert_eq!(tiles_in_range((0, 0), 5, (2, 2)).len(), 4);
+        assert!(in_bounds((99, 0), BOUNDS));
+        assert!(!in_bounds((100, 0), BOUNDS));
+    }
+
+    #[test]
+    fn direction_to_points_at_the_target() {
+        assert_eq!(direction_to((5, 5), (8, 5), BOUNDS), Direction::East);
+        assert_eq!(direction_to((5, 5), (2, 5), BOUNDS), Direction::West);
+        assert_eq!(direction_to((4, 4), (6, 0), BOUNDS), Direction::NorthEast);
+        assert_eq!(direction_to((4, 4), (2, 0), BOUNDS), Direction::NorthWest);
+        assert_eq!(direction_to((4, 4), (6, 8), BOUNDS), Direction::SouthEast);
+        assert_eq!(direction_to((4, 4), (2, 8), BOUNDS), Direction::SouthWest);
+    }
+
+    #[test]
+    fn direction_to_takes_the_short_way_around() {
+        assert_eq!(direction_to((1, 5), (98, 5), BOUNDS), Direction::West);
+        assert_eq!(direction_to((98, 5), (1, 5), BOUNDS), Direction::East);
+        assert_eq!(direction_to((4, 2), (6, 98), BOUNDS), Direction::NorthEast);
+        assert_eq!(direction_to((4, 98), (2, 2), BOUNDS), Direction::SouthWest);
+    }
+
+    #[test]
+    fn cargo_slows_down_to_half_speed() {
+        assert_eq!(loaded_speed(1000, 0, 64), 1000);
+        assert_eq!(loaded_speed(1000, 32, 64), 750);
+        assert_eq!(loaded_speed(1000, 64, 64), 500);
+        assert_eq!(loaded_speed(1000, 10, 0), 1000);
+    }
+
+    #[test]
+    fn move_cost_grows_faster_than_speed() {
+        let max_speed = 10000;
+        for speed in [1000, 2000, 2500, 5000] {
+            assert!(
+                move_energy_per_tile(2 * speed, max_speed) > 2 * move_energy_per_tile(speed, max_speed)
+            );
+        }
+        assert_eq!(move_energy_per_tile(max_speed, max_speed), 100);
+        assert_eq!(move_energy_per_tile(0, max_speed), 0);
+    }
+
+    #[test]
+    fn slow_moves_still_cost_energy() {
+        assert_eq!(move_energy_per_tile(1, 10000), 1);
+        assert_eq!(move_energy_per_tile(999, 10000), 1);
+        assert_eq!(move_energy_per_tile(1001, 10000), 2);
+        assert_eq!(move_energy_per_tile(5000, 10000), 25);
+    }
+}
@@ -1,7 +1,7 @@
 use ink::primitives::AccountId;
 use ink::prelude::{vec, vec::Vec};
 
-use crate::inventory::{ResourceType, Inventory};
+use crate::inventory::{Error, Inventory, Item, ItemId, ResourceType};
 
 pub type PlanetId = u32;
 
@@ -30,6 +30,11 @@ pub struct Planet {
     resources: Vec<ResourceType>,
     mining_rates: Vec<u32>,
     inventory: Inventory,
+    health: u32,
+    max_health: u32,
+    claimed_at: u32, // Block the ownership was claimed or last refreshed
+    wormhole_target: Option<(i32, i32)>, // Tile ships arriving on the planet are sent to
+    defense: u32,
 }
 
 impl Planet {
@@ -52,6 +57,19 @@ impl Planet {
             PlanetLevel::Fortress => 5,
         };
 
+        // damage dealt back to attackers per shot
+        let defense = match level {
+            PlanetLevel::Basic => 5,
+            PlanetLevel::Advanced => 10,
+            PlanetLevel::Fortress => 25,
+        };
+
+        let max_health = match level {
+            PlanetLevel::Basic => 100,
+            PlanetLevel::Advanced => 250,
+            PlanetLevel::Fortress => 500,
+        };
+
         Self {
             id,
             level,
@@ -60,6 +78,11 @@ impl Planet {
             resources,
             mining_rates,
             inventory: Inventory::new(inventory_size),
+            health: max_health,
+            max_health,
+            claimed_at: 0,
+            wormhole_target: None,
+            defense,
         }
     }
 
@@ -87,8 +110,78 @@ impl Planet {
         self.mining_rates.clone()
     }
 
-    pub fn set_owner(&mut self, owner: AccountId) {
+    pub fn get_inventory(&self) -> Inventory {
+        self.inventory.clone()
+    }
+
+    pub fn add_item(&mut self, item: Item) -> Result<ItemId, Error> {
+        self.inventory.add_item(item)
+    }
+
+    pub fn remove_item(&mut self, item_id: ItemId) -> Option<Item> {
+        self.inventory.remove_item(item_id)
+    }
+
+    pub fn get_defense(&self) -> u32 {
+        self.defense
+    }
+
+    pub fn get_health(&self) -> u32 {
+        self.health
+    }
+
+    pub fn get_max_health(&self) -> u32 {
+        self.max_health
+    }
+
+    // take_damage reduces the health, once it drops to zero the planet is lost:
+    // the owner is cleared and the health is restored for the next owner
+    pub fn take_damage(&mut self, damage: u32) -> bool {
+        self.health = self.health.saturating_sub(damage);
+        if self.health > 0 {
+            return false;
+        }
+        self.owner = None;
+        self.health = self.max_health;
+        true
+    }
+
+    // claim makes owner the owner of the planet as of block
+    pub fn claim(&mut self, owner: AccountId, block: u32) {
         self.owner = Some(owner);
+        self.claimed_at = block;
+    }
+
+    pub fn refresh_claim(&mut self, block: u32) {
+        self.claimed_at = block;
+    }
+
+    pub fn get_claimed_at(&self) -> u32 {
+        self.claimed_at
+    }
+
+    pub fn clear_owner(&mut self) {
+        self.owner = None;
+    }
+
+    pub fn get_wormhole_target(&self) -> Option<(i32, i32)> {
+        self.wormhole_target
+    }
+
+    pub fn set_wormhole_target(&mut self, target: Option<(i32, i32)>) {
+        self.wormhole_target = target;
+    }
+
+    // set_mining_rate changes the rate of a resource the planet offers,
+    // returns false if the planet doesn't have the resource
+    pub fn set_mining_rate(&mut self, resource_type: &ResourceType, rate: u32) -> bool {
+        match self.resources.iter().position(|resource| resource == resource_type) {
+            Some(index) => {
+                self.mining_rates[index] = rate;
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn get_mining_rate(&self, resource_type: &ResourceType) -> u32 {